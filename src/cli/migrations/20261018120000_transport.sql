@@ -0,0 +1,9 @@
+begin;
+
+create type device_transport as enum ('tcp', 'rtu', 'rtu_over_tcp', 'udp');
+
+alter table devices
+  add column transport device_transport null,
+  add column port int null;
+
+commit;
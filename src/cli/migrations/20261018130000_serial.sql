@@ -0,0 +1,10 @@
+begin;
+
+create type device_parity as enum ('none', 'odd', 'even');
+
+alter table devices
+  add column parity device_parity null,
+  add column stop_bits int null,
+  add column data_bits int null;
+
+commit;
@@ -0,0 +1,6 @@
+begin;
+
+alter table devices
+  add column identification jsonb null;
+
+commit;
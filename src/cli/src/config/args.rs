@@ -2,20 +2,109 @@
 #[command(author, version, about, long_about = None)]
 pub(crate) struct Values {
   /// Set log level to trace
-  #[arg(short, long)]
+  #[arg(short, long, global = true)]
   pub(crate) trace: bool,
 
   /// Set log level to debug
-  #[arg(short, long)]
+  #[arg(short, long, global = true)]
   pub(crate) debug: bool,
 
   /// Alternative configuration location
-  #[arg(short, long)]
+  #[arg(short, long, global = true)]
   pub(crate) config: Option<String>,
 
   /// Skip running cloud processes
   #[arg(short, long)]
   pub(crate) local: bool,
+
+  #[command(subcommand)]
+  pub(crate) command: Option<Command>,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub(crate) enum Command {
+  /// Simulate the configured modbus devices
+  Simulate,
+
+  /// Validate the configuration
+  Validate,
+
+  /// Print the JSON Schema of the configuration
+  Schema,
+
+  #[command(flatten)]
+  Diagnostic(Diagnostic),
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub(crate) enum Diagnostic {
+  /// Read measurement registers of a device kind from a destination
+  Read {
+    #[command(flatten)]
+    destination: Destination,
+
+    /// Configured device kind
+    #[arg(short, long)]
+    kind: String,
+
+    /// Only read measurement registers with these names
+    #[arg(short, long)]
+    register: Vec<String>,
+  },
+
+  /// Write raw values to holding registers of a destination
+  Write {
+    #[command(flatten)]
+    destination: Destination,
+
+    /// Address of the first holding register
+    #[arg(long)]
+    register: u16,
+
+    /// Raw register values
+    #[arg(required = true)]
+    values: Vec<u16>,
+  },
+
+  /// Match configured device kinds against a destination
+  Detect {
+    #[command(flatten)]
+    destination: Destination,
+  },
+
+  /// Run discovery once and print the matches
+  Scan,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct Destination {
+  /// Socket or ip address of a network server
+  #[arg(short, long, required_unless_present = "path")]
+  pub(crate) address: Option<String>,
+
+  /// Path of a serial port
+  #[arg(short, long, conflicts_with = "address")]
+  pub(crate) path: Option<String>,
+
+  /// Transport defaulting to tcp for addresses and rtu for paths
+  #[arg(long, value_enum)]
+  pub(crate) transport: Option<Transport>,
+
+  /// Slave id of the device
+  #[arg(short, long)]
+  pub(crate) slave: Option<u8>,
+
+  /// Baud rate of the serial line
+  #[arg(short, long)]
+  pub(crate) baud_rate: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub(crate) enum Transport {
+  Tcp,
+  RtuOverTcp,
+  Udp,
+  Rtu,
 }
 
 pub(crate) fn parse() -> Values {
@@ -21,6 +21,8 @@ pub(crate) struct Network {
   pub(crate) ip_range_start: String,
   pub(crate) ip_range_end: String,
   pub(crate) modbus_port: u16,
+  pub(crate) rtu_over_tcp_port: Option<u16>,
+  pub(crate) udp_port: Option<u16>,
 }
 
 #[derive(Debug, Clone)]
@@ -41,28 +43,55 @@ pub(crate) fn parse() -> Result<Values, ParseError> {
     tracing::warn!("Failed reading dotenv {error}");
   }
 
+  Ok(parse_with(|name| std::env::var(name))?)
+}
+
+// NOTE: diagnostics don't talk to the cloud or the db so they run with
+// whatever env there is and leave the missing required vars empty
+pub(crate) fn parse_available() -> Values {
+  if let Err(error) = dotenv::dotenv() {
+    tracing::debug!("Failed reading dotenv {error}");
+  }
+
+  match parse_with::<std::convert::Infallible>(|name| {
+    Ok(std::env::var(name).unwrap_or_default())
+  }) {
+    Ok(values) => values,
+    Err(never) => match never {},
+  }
+}
+
+fn parse_with<TError>(
+  required: impl Fn(&str) -> Result<String, TError>,
+) -> Result<Values, TError> {
   let values = Values {
     cloud: Cloud {
       ssl: std::env::var("PIDGEON_CLOUD_SSL").map_or_else(|_| false, |_| true),
-      domain: std::env::var("PIDGEON_CLOUD_DOMAIN")?,
+      domain: required("PIDGEON_CLOUD_DOMAIN")?,
       api_key: std::env::var("PIDGEON_CLOUD_API_KEY").ok(),
       id: std::env::var("PIDGEON_CLOUD_ID").ok(),
     },
     db: Db {
       ssl: std::env::var("PIDGEON_DB_SSL").map_or_else(|_| false, |_| true),
-      domain: std::env::var("PIDGEON_DB_DOMAIN")?,
+      domain: required("PIDGEON_DB_DOMAIN")?,
       port: std::env::var("PIDGEON_DB_PORT").ok(),
-      user: std::env::var("PIDGEON_DB_USER")?,
+      user: required("PIDGEON_DB_USER")?,
       password: std::env::var("PIDGEON_DB_PASSWORD").ok(),
-      name: std::env::var("PIDGEON_DB_NAME")?,
+      name: required("PIDGEON_DB_NAME")?,
     },
     network: Network {
-      ip_range_start: std::env::var("PIDGEON_NETWORK_IP_RANGE_START")?,
-      ip_range_end: std::env::var("PIDGEON_NETWORK_IP_RANGE_END")?,
+      ip_range_start: required("PIDGEON_NETWORK_IP_RANGE_START")?,
+      ip_range_end: required("PIDGEON_NETWORK_IP_RANGE_END")?,
       modbus_port: std::env::var("PIDGEON_MODBUS_PORT").map_or_else(
         |_| 502,
         |port| port.as_str().parse::<u16>().unwrap_or(502),
       ),
+      rtu_over_tcp_port: std::env::var("PIDGEON_MODBUS_RTU_OVER_TCP_PORT")
+        .ok()
+        .and_then(|port| port.as_str().parse::<u16>().ok()),
+      udp_port: std::env::var("PIDGEON_MODBUS_UDP_PORT")
+        .ok()
+        .and_then(|port| port.as_str().parse::<u16>().ok()),
     },
   };
 
@@ -1,28 +1,70 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use rust_decimal::Decimal;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::config;
 use crate::service::modbus::{self, RegisterValue};
 
 // NITPICK: optional values here with #[serde(default = ...)]
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Hardware {
   pub(crate) temperature_monitor: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Network {
   pub(crate) timeout: Option<u32>,
+  pub(crate) concurrency: Option<usize>,
+  pub(crate) ip_range_start: Option<String>,
+  pub(crate) ip_range_end: Option<String>,
+  pub(crate) modbus_port: Option<u16>,
+  pub(crate) interfaces: Option<Vec<String>>,
+  pub(crate) ports: Option<Vec<u16>>,
+  pub(crate) rtu_over_tcp_port: Option<u16>,
+  pub(crate) udp_port: Option<u16>,
+  pub(crate) ipv6: Option<bool>,
+  pub(crate) exclude: Option<Vec<String>>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SerialParity {
+  None,
+  Odd,
+  Even,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SerialLine {
+  pub(crate) baud_rate: Option<u32>,
+  pub(crate) parity: Option<SerialParity>,
+  pub(crate) stop_bits: Option<u8>,
+  pub(crate) data_bits: Option<u8>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Serial {
+  #[serde(default)]
+  pub(crate) line: SerialLine,
+  pub(crate) ports: Option<HashMap<String, SerialLine>>,
+  pub(crate) detect: Option<bool>,
+  pub(crate) candidates: Option<Vec<SerialLine>>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Db {
   pub(crate) timeout: Option<u32>,
+  pub(crate) domain: Option<String>,
+  pub(crate) port: Option<u16>,
+  pub(crate) user: Option<String>,
+  pub(crate) password: Option<String>,
+  pub(crate) name: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub(crate) enum LogLevel {
   Trace,
@@ -32,29 +74,115 @@ pub(crate) enum LogLevel {
   Error,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RegisterFunction {
+  Holding,
+  Input,
+  Coil,
+  Discrete,
+  // NOTE: read device identification where the address is the object id
+  Identification,
+}
+
+// NOTE: interval in milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct MeasurementGroup {
+  pub(crate) name: String,
+  pub(crate) interval: u32,
+  pub(crate) measurement: Vec<MeasurementRegister>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct MeasurementRegister {
   pub(crate) name: String,
   pub(crate) address: u16,
+  pub(crate) function: Option<RegisterFunction>,
   pub(crate) kind: RegisterKindStorage,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+// NOTE: measurement addresses are relative to the block address and
+// every {n} in their names is replaced with the repetition number
+// which starts from one unless configured otherwise
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct MeasurementBlock {
+  pub(crate) address: u16,
+  pub(crate) stride: u16,
+  pub(crate) count: u16,
+  pub(crate) start: Option<u16>,
+  pub(crate) measurement: Vec<MeasurementRegister>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RegisterOrder {
+  #[serde(alias = "abcd")]
+  Big,
+  #[serde(alias = "dcba")]
+  Little,
+  #[serde(alias = "cdab")]
+  WordSwapped,
+  #[serde(alias = "badc")]
+  ByteSwapped,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct StringRegisterKind {
   pub(crate) length: u16,
+  pub(crate) order: Option<RegisterOrder>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct RawRegisterKind {
   pub(crate) length: u16,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+// NOTE: decimals deserialize from numbers as well as strings so that
+// values beyond float precision can still be written exactly
+#[derive(JsonSchema)]
+#[schemars(untagged)]
+enum DecimalSchema {
+  Number(f64),
+  String(String),
+}
+
+// NOTE: values are multiplied first then scaled by the power of ten
+// in the scale register and offset last
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct NumericRegisterKind {
+  #[schemars(with = "Option<DecimalSchema>")]
   pub(crate) multiplier: Option<Decimal>,
+  #[schemars(with = "Option<DecimalSchema>")]
+  pub(crate) offset: Option<Decimal>,
+  pub(crate) scale_register: Option<u16>,
+  pub(crate) order: Option<RegisterOrder>,
+}
+
+// NOTE: variants map names to values and default to a single register
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct EnumRegisterKind {
+  pub(crate) length: Option<u16>,
+  pub(crate) order: Option<RegisterOrder>,
+  pub(crate) variants: HashMap<String, u64>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+// NOTE: flags map names to bits and default to a single register
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct BitfieldRegisterKind {
+  pub(crate) length: Option<u16>,
+  pub(crate) order: Option<RegisterOrder>,
+  pub(crate) flags: HashMap<String, u8>,
+}
+
+// NOTE: device clocks are read in the given timezone which defaults to utc
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct DateTimeRegisterKind {
+  #[schemars(with = "Option<String>")]
+  pub(crate) timezone: Option<chrono_tz::Tz>,
+  pub(crate) order: Option<RegisterOrder>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub(crate) enum RegisterKindStorage {
   U16(NumericRegisterKind),
@@ -65,69 +193,151 @@ pub(crate) enum RegisterKindStorage {
   S64(NumericRegisterKind),
   F32(NumericRegisterKind),
   F64(NumericRegisterKind),
+  U48(NumericRegisterKind),
+  Bcd16(NumericRegisterKind),
+  Bcd32(NumericRegisterKind),
+  Bcd64(NumericRegisterKind),
   String(StringRegisterKind),
   Raw(RawRegisterKind),
+  Enum(EnumRegisterKind),
+  Bitfield(BitfieldRegisterKind),
+  DateTime(DateTimeRegisterKind),
+  #[serde(rename = "iec870")]
+  Iec870Time(DateTimeRegisterKind),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct DetectRegister {
   pub(crate) address: u16,
+  pub(crate) function: Option<RegisterFunction>,
   pub(crate) kind: RegisterKindStorage,
   pub(crate) r#match: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// NOTE: patterns matched against read device identification objects
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Identification {
+  pub(crate) vendor: Option<String>,
+  pub(crate) product: Option<String>,
+  pub(crate) revision: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct DetectAlternative {
+  pub(crate) detect: Vec<DetectRegister>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct IdRegister {
   pub(crate) address: u16,
+  pub(crate) function: Option<RegisterFunction>,
   pub(crate) kind: RegisterKindStorage,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct ValueRegister {
   pub(crate) address: u16,
   pub(crate) value: Vec<u16>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) enum TimeImplementation {
   #[serde(rename = "schneider-iEM3xxx")]
   SchneideriEM3xxx,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+// NOTE: a device extending another kind inherits everything it doesn't set
+// with measurements and groups overridden by name and the rest replaced
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Device {
+  pub(crate) extend: Option<String>,
+  pub(crate) priority: Option<i32>,
+  pub(crate) max_batch_quantity: Option<u16>,
+  pub(crate) turnaround_delay: Option<u32>,
+  #[serde(default)]
   pub(crate) detect: Vec<DetectRegister>,
+  pub(crate) identification: Option<Identification>,
+  #[serde(default)]
+  pub(crate) alternatives: Vec<DetectAlternative>,
+  #[serde(default)]
   pub(crate) id: Vec<IdRegister>,
+  #[serde(default)]
   pub(crate) measurement: Vec<MeasurementRegister>,
+  #[serde(default)]
+  pub(crate) blocks: Vec<MeasurementBlock>,
+  #[serde(default)]
+  pub(crate) groups: Vec<MeasurementGroup>,
+  #[serde(default)]
   pub(crate) configuration: Vec<ValueRegister>,
+  #[serde(default)]
   pub(crate) daily: Vec<ValueRegister>,
+  #[serde(default)]
   pub(crate) nightly: Vec<ValueRegister>,
   pub(crate) time: Option<TimeImplementation>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Modbus {
   pub(crate) request_timeout: Option<u32>,
   pub(crate) batch_threshold: Option<u16>,
   pub(crate) termination_timeout: Option<u32>,
   pub(crate) congestion_backoff: Option<u32>,
   pub(crate) partial_retries: Option<u32>,
+  pub(crate) turnaround_delay: Option<u32>,
+  pub(crate) rtu_over_tcp_line: Option<SerialLine>,
   pub(crate) ping_timeout: Option<u32>,
   pub(crate) tariff_timeout: Option<u32>,
   pub(crate) time_timeout: Option<u32>,
   pub(crate) inactive_timeout: Option<u32>,
   pub(crate) discovery_timeout: Option<u32>,
   pub(crate) max_slave: Option<u8>,
+  pub(crate) sunspec: Option<bool>,
+  pub(crate) identification: Option<bool>,
+  pub(crate) library: Option<String>,
+  #[serde(default)]
   pub(crate) devices: HashMap<String, Device>,
+  #[serde(default)]
+  pub(crate) r#static: Vec<StaticDevice>,
+}
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Transport {
+  Tcp,
+  RtuOverTcp,
+  Udp,
+  Rtu,
+}
+
+// NOTE: address is either a socket address or an ip address in which case
+// the port is the one configured for the transport
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Destination {
+  pub(crate) transport: Option<Transport>,
+  pub(crate) address: Option<String>,
+  pub(crate) path: Option<String>,
+  pub(crate) line: Option<SerialLine>,
+  pub(crate) slave: Option<u8>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct StaticDevice {
+  pub(crate) kind: String,
+  #[serde(flatten)]
+  pub(crate) destination: Destination,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Cloud {
   pub(crate) timeout: Option<u32>,
   pub(crate) message_limit: Option<i64>,
+  pub(crate) domain: Option<String>,
+  pub(crate) api_key: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Schedule {
   pub(crate) discover: Option<String>,
   pub(crate) ping: Option<String>,
@@ -139,10 +349,99 @@ pub(crate) struct Schedule {
   pub(crate) nightly: Option<String>,
   pub(crate) time: Option<String>,
   pub(crate) poll: Option<String>,
+  #[schemars(with = "Option<String>")]
   pub(crate) timezone: Option<chrono_tz::Tz>,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+// NOTE: stale timeout in milliseconds
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Gateway {
+  pub(crate) enabled: Option<bool>,
+  pub(crate) address: Option<String>,
+  pub(crate) stale_timeout: Option<u32>,
+  pub(crate) units: Option<HashMap<String, u8>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SimulatorTransport {
+  Tcp,
+  Rtu,
+}
+
+// NOTE: period in milliseconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SimulatorRamp {
+  #[schemars(with = "DecimalSchema")]
+  pub(crate) min: Decimal,
+  #[schemars(with = "DecimalSchema")]
+  pub(crate) max: Decimal,
+  pub(crate) period: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SimulatorNoise {
+  #[schemars(with = "DecimalSchema")]
+  pub(crate) mean: Decimal,
+  #[schemars(with = "DecimalSchema")]
+  pub(crate) amplitude: Decimal,
+}
+
+// NOTE: step per second
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SimulatorCounter {
+  #[schemars(with = "Option<DecimalSchema>")]
+  pub(crate) start: Option<Decimal>,
+  #[schemars(with = "DecimalSchema")]
+  pub(crate) step: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SimulatorValue {
+  #[schemars(with = "DecimalSchema")]
+  Constant(Decimal),
+  Text(String),
+  Ramp(SimulatorRamp),
+  Noise(SimulatorNoise),
+  Counter(SimulatorCounter),
+}
+
+// NOTE: probabilities per request from 0 to 1
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SimulatorFaults {
+  pub(crate) timeout: Option<f64>,
+  pub(crate) exception: Option<f64>,
+  pub(crate) wrong_slave: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SimulatorDevice {
+  pub(crate) kind: String,
+  pub(crate) slave: Option<u8>,
+  pub(crate) id: Option<u64>,
+  pub(crate) detect: Option<Vec<String>>,
+  #[serde(default)]
+  pub(crate) values: HashMap<String, SimulatorValue>,
+  #[serde(default)]
+  pub(crate) faults: SimulatorFaults,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SimulatorServer {
+  pub(crate) transport: Option<SimulatorTransport>,
+  pub(crate) address: Option<String>,
+  pub(crate) link: Option<String>,
+  pub(crate) devices: Vec<SimulatorDevice>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Simulator {
+  #[serde(default)]
+  pub(crate) servers: Vec<SimulatorServer>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub(crate) struct Values {
   pub(crate) log_level: Option<LogLevel>,
   #[serde(default)]
@@ -150,6 +449,8 @@ pub(crate) struct Values {
   #[serde(default)]
   pub(crate) network: Network,
   #[serde(default)]
+  pub(crate) serial: Serial,
+  #[serde(default)]
   pub(crate) cloud: Cloud,
   #[serde(default)]
   pub(crate) db: Db,
@@ -157,6 +458,10 @@ pub(crate) struct Values {
   pub(crate) modbus: Modbus,
   #[serde(default)]
   pub(crate) schedule: Schedule,
+  #[serde(default)]
+  pub(crate) gateway: Gateway,
+  #[serde(default)]
+  pub(crate) simulator: Simulator,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -181,37 +486,218 @@ pub(crate) enum ParseError {
 
   #[error("Failed deserializing config from json")]
   DeserializetionJson(#[from] serde_json::Error),
+
+  #[error("Failed reading device library file {0}")]
+  Library(String, #[source] Box<ParseError>),
+
+  #[error("Config has {} issues", .0.len())]
+  Invalid(Vec<config::Issue>),
 }
 
-pub(crate) async fn parse_file(
+pub(crate) fn location(
   location: Option<&str>,
-) -> Result<Values, ParseError> {
-  let location = match location {
-    Some(location) => std::path::PathBuf::from(location),
+) -> Result<std::path::PathBuf, ParseError> {
+  match location {
+    Some(location) => Ok(std::path::PathBuf::from(location)),
     None => match directories::ProjectDirs::from("com", "altibiz", "pidgeon") {
-      Some(project_dirs) => project_dirs.config_dir().join("config.yaml"),
-      None => return Err(ParseError::MissingProjectDirs),
+      Some(project_dirs) => Ok(project_dirs.config_dir().join("config.yaml")),
+      None => Err(ParseError::MissingProjectDirs),
     },
+  }
+}
+
+pub(crate) async fn parse_file(
+  location: Option<&str>,
+) -> Result<Values, ParseError> {
+  let location = self::location(location)?;
+
+  let mut values = read_file::<Values>(&location).await?;
+  if let Some(library) = values.modbus.library.clone() {
+    merge_library(&mut values, &library_location(&location, &library)).await?;
+  }
+
+  Ok(values)
+}
+
+// NOTE: remote configs have no location of their own so their library
+// resolves against the local config file like the file itself would
+pub(crate) async fn parse_json(
+  json: &str,
+  location: Option<&str>,
+) -> Result<Values, ParseError> {
+  let location = self::location(location)?;
+
+  let mut parsed = serde_json::from_str::<Values>(json)?;
+  if let Some(library) = parsed.modbus.library.clone() {
+    merge_library(&mut parsed, &library_location(&location, &library)).await?;
+  }
+
+  Ok(parsed)
+}
+
+fn library_location(
+  location: &std::path::Path,
+  library: &str,
+) -> std::path::PathBuf {
+  match location.parent() {
+    Some(parent) => parent.join(library),
+    None => std::path::PathBuf::from(library),
+  }
+}
+
+async fn read_file<T: serde::de::DeserializeOwned>(
+  location: &std::path::Path,
+) -> Result<T, ParseError> {
+  let raw = tokio::fs::read_to_string(location).await?;
+  let parsed = match location.extension().and_then(|str| str.to_str()) {
+    None => return Err(ParseError::MissingExtension),
+    Some("yaml" | "yml") => serde_yaml::from_str::<T>(raw.as_str())?,
+    Some("toml") => toml::from_str::<T>(raw.as_str())?,
+    Some("json") => serde_json::from_str::<T>(raw.as_str())?,
+    Some(_) => return Err(ParseError::InvalidExtension),
   };
 
-  let values = {
-    let raw = tokio::fs::read_to_string(location.clone()).await?;
-    match location.extension().and_then(|str| str.to_str()) {
-      None => return Err(ParseError::MissingExtension),
-      Some("yaml" | "yml") => serde_yaml::from_str::<Values>(raw.as_str())?,
-      Some("toml") => toml::from_str::<Values>(raw.as_str())?,
-      Some("json") => serde_json::from_str::<Values>(raw.as_str())?,
-      Some(_) => return Err(ParseError::InvalidExtension),
+  Ok(parsed)
+}
+
+// NOTE: every library file holds one device named after the file stem
+// and devices in the main config take precedence over library devices
+async fn merge_library(
+  values: &mut Values,
+  library: &std::path::Path,
+) -> Result<(), ParseError> {
+  let mut entries = tokio::fs::read_dir(library).await?;
+  let mut locations = Vec::new();
+  while let Some(entry) = entries.next_entry().await? {
+    let location = entry.path();
+    let is_config = matches!(
+      location
+        .extension()
+        .and_then(|extension| extension.to_str()),
+      Some("yaml" | "yml" | "toml" | "json")
+    );
+    if entry.file_type().await?.is_file() && is_config {
+      locations.push(location);
+    } else {
+      tracing::trace!("Skipping library entry {}", location.display());
     }
+  }
+  locations.sort();
+
+  for location in locations {
+    let Some(kind) = location.file_stem().and_then(|stem| stem.to_str()) else {
+      continue;
+    };
+    let device = read_file::<Device>(&location).await.map_err(|error| {
+      ParseError::Library(location.display().to_string(), Box::new(error))
+    })?;
+    if values.modbus.devices.contains_key(kind) {
+      tracing::debug!("Device {} from the library is overridden", kind);
+      continue;
+    }
+    values.modbus.devices.insert(kind.to_string(), device);
+  }
+
+  Ok(())
+}
+
+pub(crate) fn resolve_device_extensions(
+  devices: HashMap<String, Device>,
+) -> HashMap<String, Device> {
+  let mut resolved = HashMap::with_capacity(devices.len());
+  for kind in devices.keys() {
+    let mut chain = Vec::new();
+    let device = resolve_device_extension(&devices, kind, &mut chain);
+    resolved.insert(kind.clone(), device);
+  }
+
+  resolved
+}
+
+fn resolve_device_extension(
+  devices: &HashMap<String, Device>,
+  kind: &String,
+  chain: &mut Vec<String>,
+) -> Device {
+  let Some(device) = devices.get(kind).cloned() else {
+    return Device::default();
   };
+  chain.push(kind.clone());
 
-  Ok(values)
+  let Some(parent) = device.extend.clone() else {
+    return device;
+  };
+  if chain.contains(&parent) {
+    tracing::warn!("Device {} extends itself through {}", kind, parent);
+    return device;
+  }
+  if !devices.contains_key(&parent) {
+    tracing::warn!("Device {} extends unknown device {}", kind, parent);
+    return device;
+  }
+
+  let parent = resolve_device_extension(devices, &parent, chain);
+  extend_device(parent, device)
 }
 
-pub(crate) async fn parse_json(json: &str) -> Result<Values, ParseError> {
-  let parsed = serde_json::from_str::<Values>(json)?;
+fn extend_device(parent: Device, child: Device) -> Device {
+  Device {
+    extend: child.extend,
+    priority: child.priority.or(parent.priority),
+    max_batch_quantity: child.max_batch_quantity.or(parent.max_batch_quantity),
+    turnaround_delay: child.turnaround_delay.or(parent.turnaround_delay),
+    detect: replace_unless_empty(parent.detect, child.detect),
+    identification: child.identification.or(parent.identification),
+    alternatives: replace_unless_empty(parent.alternatives, child.alternatives),
+    id: replace_unless_empty(parent.id, child.id),
+    measurement: override_by_name(
+      parent.measurement,
+      child.measurement,
+      |register| register.name.clone(),
+    ),
+    blocks: parent.blocks.into_iter().chain(child.blocks).collect(),
+    groups: override_by_name(parent.groups, child.groups, |group| {
+      group.name.clone()
+    }),
+    configuration: replace_unless_empty(
+      parent.configuration,
+      child.configuration,
+    ),
+    daily: replace_unless_empty(parent.daily, child.daily),
+    nightly: replace_unless_empty(parent.nightly, child.nightly),
+    time: child.time.or(parent.time),
+  }
+}
 
-  Ok(parsed)
+fn replace_unless_empty<T>(parent: Vec<T>, child: Vec<T>) -> Vec<T> {
+  if child.is_empty() {
+    parent
+  } else {
+    child
+  }
+}
+
+fn override_by_name<T>(
+  parent: Vec<T>,
+  child: Vec<T>,
+  name: impl Fn(&T) -> String,
+) -> Vec<T> {
+  let mut merged = parent;
+  for item in child {
+    match merged
+      .iter()
+      .position(|existing| name(existing) == name(&item))
+    {
+      Some(index) => {
+        if let Some(existing) = merged.get_mut(index) {
+          *existing = item;
+        }
+      }
+      None => merged.push(item),
+    }
+  }
+
+  merged
 }
 
 pub(crate) fn to_modbus_measurement_register(
@@ -219,16 +705,43 @@ pub(crate) fn to_modbus_measurement_register(
 ) -> modbus::MeasurementRegister<modbus::RegisterKindStorage> {
   modbus::MeasurementRegister::<modbus::RegisterKindStorage> {
     address: register.address,
+    function: to_modbus_register_function(register.function),
     storage: to_modbus_register_kind(register.kind),
     name: register.name,
   }
 }
 
+pub(crate) fn to_modbus_identification_registers(
+  identification: Identification,
+) -> Vec<modbus::DetectRegister<modbus::RegisterKindStorage>> {
+  [
+    (modbus::identification::VENDOR_NAME, identification.vendor),
+    (modbus::identification::PRODUCT_CODE, identification.product),
+    (modbus::identification::REVISION, identification.revision),
+  ]
+  .into_iter()
+  .filter_map(|(object, pattern)| {
+    pattern.map(|pattern| {
+      to_modbus_detect_register(DetectRegister {
+        address: object,
+        function: Some(RegisterFunction::Identification),
+        kind: RegisterKindStorage::String(StringRegisterKind {
+          length: modbus::identification::OBJECT_LENGTH,
+          order: None,
+        }),
+        r#match: pattern,
+      })
+    })
+  })
+  .collect()
+}
+
 pub(crate) fn to_modbus_detect_register(
   register: DetectRegister,
 ) -> modbus::DetectRegister<modbus::RegisterKindStorage> {
   modbus::DetectRegister::<modbus::RegisterKindStorage> {
     address: register.address,
+    function: to_modbus_register_function(register.function),
     storage: to_modbus_register_kind(register.kind),
     r#match: match regex::Regex::new(register.r#match.as_str()) {
       Ok(regex) => either::Either::Right(regex),
@@ -242,6 +755,7 @@ pub(crate) fn to_modbus_id_register(
 ) -> modbus::IdRegister<modbus::RegisterKindStorage> {
   modbus::IdRegister::<modbus::RegisterKindStorage> {
     address: register.address,
+    function: to_modbus_register_function(register.function),
     storage: to_modbus_register_kind(register.kind),
   }
 }
@@ -253,64 +767,272 @@ pub(crate) fn to_modbus_value_register(
     address: register.address,
     storage: modbus::RegisterValueStorage::Raw(RegisterValue::<_> {
       value: register.value,
+      order: modbus::encoding::Order::Big,
       timestamp: chrono::Utc::now(),
     }),
   }
 }
 
+pub(crate) fn to_modbus_register_function(
+  function: Option<RegisterFunction>,
+) -> modbus::span::Function {
+  match function {
+    None | Some(RegisterFunction::Holding) => {
+      modbus::span::Function::HoldingRegisters
+    }
+    Some(RegisterFunction::Input) => modbus::span::Function::InputRegisters,
+    Some(RegisterFunction::Coil) => modbus::span::Function::Coils,
+    Some(RegisterFunction::Discrete) => modbus::span::Function::DiscreteInputs,
+    Some(RegisterFunction::Identification) => {
+      modbus::span::Function::DeviceIdentification
+    }
+  }
+}
+
+pub(crate) fn to_modbus_register_order(
+  order: Option<RegisterOrder>,
+) -> modbus::encoding::Order {
+  match order {
+    None | Some(RegisterOrder::Big) => modbus::encoding::Order::Big,
+    Some(RegisterOrder::Little) => modbus::encoding::Order::Little,
+    Some(RegisterOrder::WordSwapped) => modbus::encoding::Order::WordSwapped,
+    Some(RegisterOrder::ByteSwapped) => modbus::encoding::Order::ByteSwapped,
+  }
+}
+
+// NOTE: unsupported stop and data bits fall back to the default line
+pub(crate) fn to_modbus_line(
+  line: SerialLine,
+  default: modbus::connection::Line,
+) -> modbus::connection::Line {
+  modbus::connection::Line {
+    baud_rate: line.baud_rate.unwrap_or(default.baud_rate),
+    parity: match line.parity {
+      None => default.parity,
+      Some(SerialParity::None) => tokio_serial::Parity::None,
+      Some(SerialParity::Odd) => tokio_serial::Parity::Odd,
+      Some(SerialParity::Even) => tokio_serial::Parity::Even,
+    },
+    stop_bits: match line.stop_bits {
+      Some(1) => tokio_serial::StopBits::One,
+      Some(2) => tokio_serial::StopBits::Two,
+      _ => default.stop_bits,
+    },
+    data_bits: match line.data_bits {
+      Some(5) => tokio_serial::DataBits::Five,
+      Some(6) => tokio_serial::DataBits::Six,
+      Some(7) => tokio_serial::DataBits::Seven,
+      Some(8) => tokio_serial::DataBits::Eight,
+      _ => default.data_bits,
+    },
+  }
+}
+
 pub(crate) fn to_modbus_register_kind(
   register: RegisterKindStorage,
 ) -> modbus::RegisterKindStorage {
   match register {
-    RegisterKindStorage::U16(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::U16(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::U32(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::U32(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::U64(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::U64(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::S16(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::S16(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::S32(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::S32(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::S64(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::S64(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::F32(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::F32(modbus::NumericRegisterKind {
-        multiplier,
-      })
-    }
-    RegisterKindStorage::F64(NumericRegisterKind { multiplier }) => {
-      modbus::RegisterKindStorage::F64(modbus::NumericRegisterKind {
-        multiplier,
+    RegisterKindStorage::U16(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::U16(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::U32(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::U32(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::U64(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::U64(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::S16(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::S16(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::S32(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::S32(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::S64(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::S64(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::F32(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::F32(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::F64(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::F64(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::U48(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::U48(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::Bcd16(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::Bcd16(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::Bcd32(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::Bcd32(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::Bcd64(NumericRegisterKind {
+      multiplier,
+      offset,
+      scale_register,
+      order,
+    }) => modbus::RegisterKindStorage::Bcd64(modbus::NumericRegisterKind {
+      multiplier,
+      offset,
+      order: to_modbus_register_order(order),
+      scale_register,
+      not_implemented: None,
+    }),
+    RegisterKindStorage::String(StringRegisterKind { length, order }) => {
+      modbus::RegisterKindStorage::String(modbus::StringRegisterKind {
+        length,
+        order: to_modbus_register_order(order),
       })
     }
-    RegisterKindStorage::String(StringRegisterKind { length }) => {
-      modbus::RegisterKindStorage::String(modbus::StringRegisterKind { length })
-    }
     RegisterKindStorage::Raw(RawRegisterKind { length }) => {
       modbus::RegisterKindStorage::Raw(modbus::RawRegisterKind { length })
     }
+    RegisterKindStorage::Enum(EnumRegisterKind {
+      length,
+      order,
+      variants,
+    }) => modbus::RegisterKindStorage::Enum(modbus::EnumRegisterKind {
+      length: to_unsigned_register_length(length),
+      order: to_modbus_register_order(order),
+      variants: Arc::new(
+        variants
+          .into_iter()
+          .map(|(name, value)| (value, name))
+          .collect(),
+      ),
+    }),
+    RegisterKindStorage::Bitfield(BitfieldRegisterKind {
+      length,
+      order,
+      flags,
+    }) => modbus::RegisterKindStorage::Bitfield(modbus::BitfieldRegisterKind {
+      length: to_unsigned_register_length(length),
+      order: to_modbus_register_order(order),
+      flags: Arc::new(
+        flags.into_iter().map(|(name, bit)| (bit, name)).collect(),
+      ),
+    }),
+    RegisterKindStorage::DateTime(kind) => {
+      modbus::RegisterKindStorage::DateTime(to_modbus_date_time_kind(kind))
+    }
+    RegisterKindStorage::Iec870Time(kind) => {
+      modbus::RegisterKindStorage::Iec870Time(to_modbus_date_time_kind(kind))
+    }
+  }
+}
+
+fn to_modbus_date_time_kind(
+  DateTimeRegisterKind { timezone, order }: DateTimeRegisterKind,
+) -> modbus::DateTimeRegisterKind {
+  modbus::DateTimeRegisterKind {
+    timezone: timezone.unwrap_or(chrono_tz::UTC),
+    order: to_modbus_register_order(order),
   }
 }
 
+// NOTE: enums and bitfields are decoded into an unsigned 64 bit integer
+fn to_unsigned_register_length(length: Option<u16>) -> u16 {
+  length.unwrap_or(1).clamp(1, 4)
+}
+
 pub(crate) fn make_ip_range(start: String, end: String) -> ipnet::IpAddrRange {
   let (start, end) = match (start.parse(), end.parse()) {
     (Ok(start), Ok(end)) => (start, end),
@@ -327,6 +1049,166 @@ pub(crate) fn make_ip_range(start: String, end: String) -> ipnet::IpAddrRange {
   ipnet::IpAddrRange::from(ipnet::Ipv4AddrRange::new(start, end))
 }
 
+pub(crate) fn make_socket_address(
+  address: Option<String>,
+  default: std::net::SocketAddr,
+) -> std::net::SocketAddr {
+  match address {
+    None => default,
+    Some(address) => match address.parse() {
+      Ok(address) => address,
+      Err(error) => {
+        tracing::warn!("Invalid socket address {} {}", address, error);
+        default
+      }
+    },
+  }
+}
+
+// NOTE: no interfaces means the default one and a star means all of them
+pub(crate) fn to_network_interfaces(
+  interfaces: Option<Vec<String>>,
+) -> config::NetworkInterfaces {
+  match interfaces {
+    None => config::NetworkInterfaces::Default,
+    Some(interfaces) if interfaces.iter().any(|name| name == "*") => {
+      config::NetworkInterfaces::All
+    }
+    Some(interfaces) => config::NetworkInterfaces::Selected(interfaces),
+  }
+}
+
+pub(crate) fn to_ip_nets(nets: Option<Vec<String>>) -> Vec<ipnet::IpNet> {
+  nets
+    .unwrap_or_default()
+    .into_iter()
+    .filter_map(|net| {
+      if let Ok(net) = net.parse::<ipnet::IpNet>() {
+        return Some(net);
+      }
+      match net.parse::<std::net::IpAddr>() {
+        Ok(ip) => Some(ipnet::IpNet::from(ip)),
+        Err(error) => {
+          tracing::warn!("Invalid ip network {} {}", net, error);
+          None
+        }
+      }
+    })
+    .collect()
+}
+
+pub(crate) fn to_modbus_static_device(
+  device: StaticDevice,
+  network: &config::Network,
+  line: modbus::connection::Line,
+) -> Option<config::StaticDevice> {
+  let Some(destination) =
+    to_modbus_destination(device.destination, network, line)
+  else {
+    tracing::warn!("Static {} device skipped", device.kind);
+    return None;
+  };
+
+  Some(config::StaticDevice {
+    kind: device.kind,
+    destination,
+  })
+}
+
+pub(crate) fn to_modbus_destination(
+  destination: Destination,
+  network: &config::Network,
+  line: modbus::connection::Line,
+) -> Option<modbus::Destination> {
+  let transport = match (
+    destination.transport,
+    &destination.address,
+    &destination.path,
+  ) {
+    (Some(transport), _, _) => transport,
+    (None, Some(_), _) => Transport::Tcp,
+    (None, None, Some(_)) => Transport::Rtu,
+    (None, None, None) => {
+      tracing::warn!("Destination without address or path");
+      return None;
+    }
+  };
+
+  let socket = |port: Option<u16>| {
+    let address = destination.address.as_deref()?;
+    if let Ok(address) = address.parse::<std::net::SocketAddr>() {
+      return Some(address);
+    }
+    match address.parse::<std::net::IpAddr>() {
+      Ok(ip) => Some(std::net::SocketAddr::new(ip, port.unwrap_or(502))),
+      Err(error) => {
+        tracing::warn!("Invalid destination address {} {}", address, error);
+        None
+      }
+    }
+  };
+
+  let modbus_device = match transport {
+    Transport::Tcp => {
+      socket(Some(network.modbus_port)).map(modbus::connection::Device::Tcp)
+    }
+    Transport::RtuOverTcp => socket(network.rtu_over_tcp_port)
+      .map(modbus::connection::Device::RtuOverTcp),
+    Transport::Udp => {
+      socket(network.udp_port).map(modbus::connection::Device::Udp)
+    }
+    Transport::Rtu => {
+      destination
+        .path
+        .clone()
+        .map(|path| modbus::connection::Device::Rtu {
+          path,
+          line: destination.line.clone().map_or(line, |destination_line| {
+            to_modbus_line(destination_line, line)
+          }),
+        })
+    }
+  };
+  let Some(modbus_device) = modbus_device else {
+    tracing::warn!("Destination missing {:?} server details", transport);
+    return None;
+  };
+
+  Some(modbus::Destination {
+    device: modbus_device,
+    slave: destination.slave,
+  })
+}
+
+pub(crate) fn to_simulator_value(
+  value: SimulatorValue,
+) -> config::SimulatorValue {
+  match value {
+    SimulatorValue::Constant(value) => config::SimulatorValue::Constant(value),
+    SimulatorValue::Text(value) => config::SimulatorValue::Text(value),
+    SimulatorValue::Ramp(SimulatorRamp { min, max, period }) => {
+      config::SimulatorValue::Ramp {
+        min,
+        max,
+        period: milliseconds_to_chrono(period),
+      }
+    }
+    SimulatorValue::Noise(SimulatorNoise { mean, amplitude }) => {
+      config::SimulatorValue::Noise { mean, amplitude }
+    }
+    SimulatorValue::Counter(SimulatorCounter { start, step }) => {
+      config::SimulatorValue::Counter {
+        start: start.unwrap_or(Decimal::ZERO),
+        step,
+      }
+    }
+  }
+}
+
+pub(crate) fn to_probability(probability: Option<f64>) -> f64 {
+  probability.unwrap_or(0.0).clamp(0.0, 1.0)
+}
+
 pub(crate) fn milliseconds_to_chrono(milliseconds: u32) -> chrono::Duration {
   chrono::Duration::milliseconds(milliseconds as i64)
 }
@@ -367,3 +1249,173 @@ pub(crate) fn string_to_cron(
   )
   .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[allow(clippy::unwrap_used, reason = "test devices are valid")]
+  fn device(raw: &str) -> Device {
+    toml::from_str(raw).unwrap()
+  }
+
+  fn devices(raw: &[(&str, &str)]) -> HashMap<String, Device> {
+    raw
+      .iter()
+      .map(|(kind, raw)| ((*kind).to_owned(), device(raw)))
+      .collect()
+  }
+
+  fn names(registers: &[MeasurementRegister]) -> Vec<(&str, u16)> {
+    registers
+      .iter()
+      .map(|register| (register.name.as_str(), register.address))
+      .collect()
+  }
+
+  const PARENT: &str = r#"
+    max_batch_quantity = 50
+
+    [[measurement]]
+    name = "power"
+    address = 1
+    kind = { u16 = {} }
+
+    [[measurement]]
+    name = "energy"
+    address = 2
+    kind = { u16 = {} }
+
+    [[blocks]]
+    address = 100
+    stride = 2
+    count = 2
+    measurement = []
+  "#;
+
+  #[test]
+  fn extensions_override_measurements_by_name() {
+    let resolved = resolve_device_extensions(devices(&[
+      ("parent", PARENT),
+      (
+        "child",
+        r#"
+          extend = "parent"
+
+          [[measurement]]
+          name = "energy"
+          address = 20
+          kind = { u32 = {} }
+
+          [[measurement]]
+          name = "current"
+          address = 3
+          kind = { u16 = {} }
+
+          [[blocks]]
+          address = 200
+          stride = 2
+          count = 2
+          measurement = []
+        "#,
+      ),
+    ]));
+
+    let child = resolved.get("child");
+    assert_eq!(
+      child.map(|child| names(&child.measurement)),
+      Some(vec![("power", 1), ("energy", 20), ("current", 3)])
+    );
+    assert_eq!(
+      child.map(|child| child
+        .blocks
+        .iter()
+        .map(|block| block.address)
+        .collect::<Vec<_>>()),
+      Some(vec![100, 200])
+    );
+    assert_eq!(child.and_then(|child| child.max_batch_quantity), Some(50));
+  }
+
+  #[test]
+  fn extension_cycles_stop_at_the_repeated_device() {
+    let resolved = resolve_device_extensions(devices(&[
+      ("first", "extend = \"second\"\nmax_batch_quantity = 10"),
+      ("second", "extend = \"first\"\nturnaround_delay = 20"),
+      ("lonely", "extend = \"missing\"\nmax_batch_quantity = 30"),
+    ]));
+
+    let first = resolved.get("first");
+    assert_eq!(first.and_then(|first| first.max_batch_quantity), Some(10));
+    assert_eq!(first.and_then(|first| first.turnaround_delay), Some(20));
+    let second = resolved.get("second");
+    assert_eq!(
+      second.and_then(|second| second.max_batch_quantity),
+      Some(10)
+    );
+    assert_eq!(
+      resolved
+        .get("lonely")
+        .and_then(|lonely| lonely.max_batch_quantity),
+      Some(30)
+    );
+  }
+
+  #[tokio::test]
+  #[allow(clippy::unwrap_used, reason = "the test directory is writable")]
+  async fn main_config_wins_over_the_library() {
+    let directory =
+      std::env::temp_dir().join(format!("pidgeon-{}", std::process::id()));
+    let library = directory.join("library");
+    tokio::fs::create_dir_all(&library).await.unwrap();
+    tokio::fs::write(library.join("meter.toml"), "max_batch_quantity = 10")
+      .await
+      .unwrap();
+    tokio::fs::write(library.join("other.yaml"), "max_batch_quantity: 30")
+      .await
+      .unwrap();
+    tokio::fs::write(library.join("README.md"), "# devices")
+      .await
+      .unwrap();
+    tokio::fs::write(library.join(".gitkeep"), "")
+      .await
+      .unwrap();
+    let location = directory.join("config.toml");
+    tokio::fs::write(
+      &location,
+      "[modbus]\nlibrary = \"library\"\n\
+        [modbus.devices.meter]\nmax_batch_quantity = 20\n",
+    )
+    .await
+    .unwrap();
+
+    let location = location.to_str().unwrap();
+    let from_file = parse_file(Some(location)).await.unwrap();
+    let from_json = parse_json(
+      &serde_json::to_string(&serde_json::json!({
+        "modbus": {
+          "library": "library",
+          "devices": { "meter": { "max_batch_quantity": 20 } }
+        }
+      }))
+      .unwrap(),
+      Some(location),
+    )
+    .await
+    .unwrap();
+    tokio::fs::remove_dir_all(&directory).await.unwrap();
+
+    for values in [from_file, from_json] {
+      let quantity = |kind: &str| {
+        values
+          .modbus
+          .devices
+          .get(kind)
+          .and_then(|device| device.max_batch_quantity)
+      };
+      assert_eq!(quantity("meter"), Some(20));
+      assert_eq!(quantity("other"), Some(30));
+      assert_eq!(values.modbus.devices.len(), 2);
+    }
+  }
+}
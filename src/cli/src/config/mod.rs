@@ -1,15 +1,21 @@
 mod args;
 mod env;
 mod file;
+mod validate;
+mod watch;
 
-use std::{collections::HashMap, fs, sync::Arc};
+use std::{collections::HashMap, fs, net::SocketAddr, sync::Arc};
 
-use ipnet::IpAddrRange;
+use ipnet::{IpAddrRange, IpNet};
+use rust_decimal::Decimal;
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 
 use crate::service::modbus;
 
+pub(crate) use args::{Command, Destination as CommandDestination, Diagnostic};
+pub(crate) use validate::Issue;
+
 #[derive(Debug, Clone)]
 pub(crate) struct Db {
   pub(crate) timeout: chrono::Duration,
@@ -26,6 +32,28 @@ pub(crate) struct Network {
   pub(crate) timeout: chrono::Duration,
   pub(crate) ip_range: IpAddrRange,
   pub(crate) modbus_port: u16,
+  pub(crate) rtu_over_tcp_port: Option<u16>,
+  pub(crate) udp_port: Option<u16>,
+  pub(crate) concurrency: usize,
+  pub(crate) interfaces: NetworkInterfaces,
+  pub(crate) ports: Vec<u16>,
+  pub(crate) ipv6: bool,
+  pub(crate) exclude: Vec<IpNet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NetworkInterfaces {
+  Default,
+  All,
+  Selected(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Serial {
+  pub(crate) line: modbus::connection::Line,
+  pub(crate) ports: HashMap<String, modbus::connection::Line>,
+  pub(crate) detect: bool,
+  pub(crate) candidates: Vec<modbus::connection::Line>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,13 +71,30 @@ pub(crate) struct Cloud {
   pub(crate) id: String,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct MeasurementGroup {
+  pub(crate) name: String,
+  pub(crate) interval: chrono::Duration,
+  pub(crate) measurement:
+    Vec<modbus::MeasurementRegister<modbus::RegisterKindStorage>>,
+}
+
+// NOTE: a device matches when all of its detect registers or all registers
+// of any of its alternatives match and when multiple devices match the one
+// with the highest priority and then the most detect registers is picked
 #[derive(Debug, Clone)]
 pub(crate) struct Device {
   pub(crate) kind: String,
+  pub(crate) priority: i32,
+  pub(crate) max_batch_quantity: Option<u16>,
+  pub(crate) turnaround_delay: Option<chrono::Duration>,
   pub(crate) id: Vec<modbus::IdRegister<modbus::RegisterKindStorage>>,
   pub(crate) detect: Vec<modbus::DetectRegister<modbus::RegisterKindStorage>>,
+  pub(crate) alternatives:
+    Vec<Vec<modbus::DetectRegister<modbus::RegisterKindStorage>>>,
   pub(crate) measurement:
     Vec<modbus::MeasurementRegister<modbus::RegisterKindStorage>>,
+  pub(crate) groups: Vec<MeasurementGroup>,
   pub(crate) configuration:
     Vec<modbus::ValueRegister<modbus::RegisterValueStorage>>,
   pub(crate) daily: Vec<modbus::ValueRegister<modbus::RegisterValueStorage>>,
@@ -64,13 +109,32 @@ pub(crate) struct Modbus {
   pub(crate) termination_timeout: chrono::Duration,
   pub(crate) congestion_backoff: chrono::Duration,
   pub(crate) partial_retries: u32,
+  pub(crate) turnaround_delay: chrono::Duration,
+  pub(crate) rtu_over_tcp_line: modbus::connection::Line,
   pub(crate) ping_timeout: chrono::Duration,
   pub(crate) tariff_timeout: chrono::Duration,
   pub(crate) time_timeout: chrono::Duration,
   pub(crate) inactive_timeout: chrono::Duration,
   pub(crate) discovery_timeout: chrono::Duration,
   pub(crate) max_slave: u8,
+  pub(crate) sunspec: bool,
+  pub(crate) identification: bool,
   pub(crate) devices: HashMap<String, Device>,
+  pub(crate) r#static: Vec<StaticDevice>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StaticDevice {
+  pub(crate) kind: String,
+  pub(crate) destination: modbus::Destination,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Gateway {
+  pub(crate) enabled: bool,
+  pub(crate) address: SocketAddr,
+  pub(crate) stale_timeout: chrono::Duration,
+  pub(crate) units: HashMap<String, u8>,
 }
 
 #[derive(Debug, Clone)]
@@ -93,13 +157,70 @@ pub(crate) struct Values {
   pub(crate) cloud: Cloud,
   pub(crate) db: Db,
   pub(crate) network: Network,
+  pub(crate) serial: Serial,
   pub(crate) modbus: Modbus,
+  pub(crate) gateway: Gateway,
   pub(crate) hardware: Hardware,
   pub(crate) schedule: Schedule,
   pub(crate) log_level: tracing::level_filters::LevelFilter,
   pub(crate) local: bool,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) enum SimulatorValue {
+  Constant(Decimal),
+  Text(String),
+  Ramp {
+    min: Decimal,
+    max: Decimal,
+    period: chrono::Duration,
+  },
+  Noise {
+    mean: Decimal,
+    amplitude: Decimal,
+  },
+  Counter {
+    start: Decimal,
+    step: Decimal,
+  },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SimulatorFaults {
+  pub(crate) timeout: f64,
+  pub(crate) exception: f64,
+  pub(crate) wrong_slave: f64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimulatorDevice {
+  pub(crate) kind: String,
+  pub(crate) slave: u8,
+  pub(crate) id: u64,
+  pub(crate) detect: Option<Vec<String>>,
+  pub(crate) values: HashMap<String, SimulatorValue>,
+  pub(crate) faults: SimulatorFaults,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum SimulatorTransport {
+  Tcp(SocketAddr),
+  Rtu(Option<String>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimulatorServer {
+  pub(crate) transport: SimulatorTransport,
+  pub(crate) devices: Vec<SimulatorDevice>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Simulation {
+  pub(crate) log_level: tracing::level_filters::LevelFilter,
+  pub(crate) devices: HashMap<String, Device>,
+  pub(crate) servers: Vec<SimulatorServer>,
+}
+
 #[derive(Debug, Clone)]
 struct Unparsed {
   from_args: args::Values,
@@ -110,8 +231,19 @@ struct Unparsed {
 #[derive(Debug, Clone)]
 pub(crate) struct Manager {
   lock: Arc<Mutex<Unparsed>>,
+  changes: broadcast::Sender<Changes>,
 }
 
+// NOTE: paths of the file values that changed in a swap like
+// modbus.devices.meter or schedule.timezone
+pub(crate) type Changes = Arc<Vec<String>>;
+
+// NOTE: subscribers that fall this far behind get lagged and should
+// treat everything as changed
+const CHANGES_CAPACITY: usize = 16;
+
+const DIAGNOSTIC_ID: &str = "pidgeon-diagnostic";
+
 #[derive(Debug, Error)]
 pub(crate) enum ReadError {
   #[error("Failed reading file")]
@@ -131,78 +263,156 @@ impl Manager {
   pub(crate) async fn new() -> Result<Self, ReadError> {
     let config = Self::read().await?;
 
+    let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
     let config_manager = Self {
       lock: Arc::new(Mutex::new(config)),
+      changes,
     };
 
     Ok(config_manager)
   }
 
+  // NOTE: diagnostics run on technician machines without the daemon env
+  // or a devicetree serial number so they get a fixed id instead
+  pub(crate) async fn diagnostic() -> Result<Self, ReadError> {
+    let from_args = args::parse();
+    let mut from_env = env::parse_available();
+    from_env
+      .cloud
+      .id
+      .get_or_insert_with(|| DIAGNOSTIC_ID.to_owned());
+    let from_file = file::parse_file(from_args.config.as_deref()).await?;
+    check(&from_file)?;
+
+    let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
+    Ok(Self {
+      lock: Arc::new(Mutex::new(Unparsed {
+        from_args,
+        from_env,
+        from_file,
+      })),
+      changes,
+    })
+  }
+
   pub(crate) async fn values(&self) -> Values {
     let config = self.lock.lock().await.clone();
 
     Self::parse(config)
   }
 
+  pub(crate) fn subscribe(&self) -> broadcast::Receiver<Changes> {
+    self.changes.subscribe()
+  }
+
   #[tracing::instrument(skip(self))]
   pub(crate) async fn reload(&self) -> Values {
-    let config = {
-      let mut values = self.lock.lock().await;
-      let from_file =
-        file::parse_file(values.from_args.config.as_deref()).await;
-      match from_file {
-        Ok(from_file) => values.from_file = from_file,
-        Err(error) => {
-          tracing::error!("Failed parsing config file {}", error)
-        }
+    let location = self.lock.lock().await.from_args.config.clone();
+    match file::parse_file(location.as_deref()).await {
+      Ok(from_file) if check(&from_file).is_ok() => self.swap(from_file).await,
+      Ok(_) => {
+        tracing::error!("Rejected invalid config file");
+        self.values().await
       }
-      values.clone()
-    };
-
-    Self::parse(config)
+      Err(error) => {
+        tracing::error!("Failed parsing config file {}", error);
+        self.values().await
+      }
+    }
   }
 
   #[tracing::instrument(skip(self))]
   pub(crate) async fn reload_json(&self, json: &str) -> Values {
-    let config = {
-      let mut values = self.lock.lock().await;
-      let from_file = file::parse_json(json).await;
-      match from_file {
-        Ok(from_file) => values.from_file = from_file,
-        Err(error) => {
-          tracing::error!("Failed parsing config json {}", error)
-        }
+    let location = self.lock.lock().await.from_args.config.clone();
+    match file::parse_json(json, location.as_deref()).await {
+      Ok(from_file) if check(&from_file).is_ok() => self.swap(from_file).await,
+      Ok(_) => {
+        tracing::error!("Rejected invalid config json");
+        self.values().await
+      }
+      Err(error) => {
+        tracing::error!("Failed parsing config json {}", error);
+        self.values().await
       }
-      values.clone()
+    }
+  }
+
+  // NOTE: the file is parsed and validated before taking the lock and
+  // then swapped whole so readers never see a partially applied config
+  async fn swap(&self, from_file: file::Values) -> Values {
+    let (config, changes) = {
+      let mut values = self.lock.lock().await;
+      let changes = log_changes(&values.from_file, &from_file);
+      values.from_file = from_file;
+      (values.clone(), changes)
     };
 
+    // NOTE: sending only fails when nobody is subscribed
+    if !changes.is_empty() {
+      let _ = self.changes.send(Arc::new(changes));
+    }
+
     Self::parse(config)
   }
 
+  // NOTE: file values override the env ones so that they can be changed
+  // without restarting through a reload or the remote config
   fn parse(config: Unparsed) -> Values {
+    let modbus_port = config
+      .from_file
+      .network
+      .modbus_port
+      .unwrap_or(config.from_env.network.modbus_port);
+    let network = Network {
+      timeout: file::milliseconds_to_chrono(
+        config.from_file.network.timeout.unwrap_or(30_000),
+      ),
+      ip_range: file::make_ip_range(
+        config
+          .from_file
+          .network
+          .ip_range_start
+          .clone()
+          .unwrap_or(config.from_env.network.ip_range_start),
+        config
+          .from_file
+          .network
+          .ip_range_end
+          .clone()
+          .unwrap_or(config.from_env.network.ip_range_end),
+      ),
+      modbus_port,
+      rtu_over_tcp_port: config
+        .from_file
+        .network
+        .rtu_over_tcp_port
+        .or(config.from_env.network.rtu_over_tcp_port),
+      udp_port: config
+        .from_file
+        .network
+        .udp_port
+        .or(config.from_env.network.udp_port),
+      concurrency: config.from_file.network.concurrency.unwrap_or(256).max(1),
+      interfaces: file::to_network_interfaces(
+        config.from_file.network.interfaces.clone(),
+      ),
+      ports: config
+        .from_file
+        .network
+        .ports
+        .clone()
+        .filter(|ports| !ports.is_empty())
+        .unwrap_or_else(|| vec![modbus_port]),
+      ipv6: config.from_file.network.ipv6.unwrap_or(false),
+      exclude: file::to_ip_nets(config.from_file.network.exclude.clone()),
+    };
+    let serial_line = file::to_modbus_line(
+      config.from_file.serial.line.clone(),
+      modbus::connection::Line::default(),
+    );
+
     Values {
-      log_level: if config.from_args.trace {
-        tracing::level_filters::LevelFilter::TRACE
-      } else if config.from_args.debug {
-        tracing::level_filters::LevelFilter::DEBUG
-      } else if let Some(log_level) = config.from_file.log_level {
-        match log_level {
-          file::LogLevel::Trace => tracing::level_filters::LevelFilter::TRACE,
-          file::LogLevel::Debug => tracing::level_filters::LevelFilter::DEBUG,
-          file::LogLevel::Info => tracing::level_filters::LevelFilter::INFO,
-          file::LogLevel::Warn => tracing::level_filters::LevelFilter::WARN,
-          file::LogLevel::Error => tracing::level_filters::LevelFilter::ERROR,
-        }
-      } else {
-        #[cfg(debug_assertions)]
-        {
-          tracing::level_filters::LevelFilter::DEBUG
-        }
-        #[cfg(not(debug_assertions))]
-        {
-          tracing::level_filters::LevelFilter::INFO
-        }
-      },
+      log_level: parse_log_level(&config.from_args, &config.from_file),
       local: config.from_args.local,
       schedule: Schedule {
         discover: file::string_to_cron(
@@ -260,8 +470,16 @@ impl Manager {
         ),
         message_limit: config.from_file.cloud.message_limit.unwrap_or(10000),
         ssl: config.from_env.cloud.ssl,
-        domain: config.from_env.cloud.domain,
-        api_key: config.from_env.cloud.api_key,
+        domain: config
+          .from_file
+          .cloud
+          .domain
+          .unwrap_or(config.from_env.cloud.domain),
+        api_key: config
+          .from_file
+          .cloud
+          .api_key
+          .or(config.from_env.cloud.api_key),
         id: config.from_env.cloud.id.unwrap_or_else(|| {
           #[allow(
             clippy::unwrap_used,
@@ -282,25 +500,58 @@ impl Manager {
           config.from_file.db.timeout.unwrap_or(30_000),
         ),
         ssl: config.from_env.db.ssl,
-        domain: config.from_env.db.domain,
-        port: config
-          .from_env
+        domain: config
+          .from_file
+          .db
+          .domain
+          .unwrap_or(config.from_env.db.domain),
+        port: config.from_file.db.port.or_else(|| {
+          config
+            .from_env
+            .db
+            .port
+            .and_then(|port| port.parse::<u16>().ok())
+        }),
+        user: config.from_file.db.user.unwrap_or(config.from_env.db.user),
+        password: config
+          .from_file
           .db
-          .port
-          .and_then(|port| port.parse::<u16>().ok()),
-        user: config.from_env.db.user,
-        password: config.from_env.db.password,
-        name: config.from_env.db.name,
+          .password
+          .or(config.from_env.db.password),
+        name: config.from_file.db.name.unwrap_or(config.from_env.db.name),
       },
-      network: Network {
-        timeout: file::milliseconds_to_chrono(
-          config.from_file.network.timeout.unwrap_or(30_000),
+      serial: {
+        let line = serial_line;
+        Serial {
+          line,
+          ports: config
+            .from_file
+            .serial
+            .ports
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(path, port)| (path, file::to_modbus_line(port, line)))
+            .collect(),
+          detect: config.from_file.serial.detect.unwrap_or(false),
+          candidates: match config.from_file.serial.candidates {
+            Some(candidates) => candidates
+              .into_iter()
+              .map(|candidate| file::to_modbus_line(candidate, line))
+              .collect(),
+            None => default_serial_candidates(),
+          },
+        }
+      },
+      gateway: Gateway {
+        enabled: config.from_file.gateway.enabled.unwrap_or(false),
+        address: file::make_socket_address(
+          config.from_file.gateway.address,
+          ([0, 0, 0, 0], 502).into(),
         ),
-        ip_range: file::make_ip_range(
-          config.from_env.network.ip_range_start,
-          config.from_env.network.ip_range_end,
+        stale_timeout: file::milliseconds_to_chrono(
+          config.from_file.gateway.stale_timeout.unwrap_or(5 * 60 * 1000),
         ),
-        modbus_port: config.from_env.network.modbus_port,
+        units: config.from_file.gateway.units.unwrap_or_default(),
       },
       modbus: Modbus {
         request_timeout: file::milliseconds_to_chrono(
@@ -318,6 +569,14 @@ impl Manager {
           config.from_file.modbus.congestion_backoff.unwrap_or(1000),
         ),
         partial_retries: config.from_file.modbus.partial_retries.unwrap_or(10),
+        turnaround_delay: file::milliseconds_to_chrono(
+          config.from_file.modbus.turnaround_delay.unwrap_or(100),
+        ),
+        rtu_over_tcp_line: match config.from_file.modbus.rtu_over_tcp_line.clone()
+        {
+          Some(line) => file::to_modbus_line(line, serial_line),
+          None => serial_line,
+        },
         ping_timeout: file::milliseconds_to_chrono(
           config.from_file.modbus.ping_timeout.unwrap_or(30_000),
         ),
@@ -338,56 +597,24 @@ impl Manager {
           config.from_file.modbus.discovery_timeout.unwrap_or(30_000),
         ),
         max_slave: config.from_file.modbus.max_slave.unwrap_or(25),
-        devices: config
+        sunspec: config.from_file.modbus.sunspec.unwrap_or(true),
+        identification: config
+          .from_file
+          .modbus
+          .identification
+          .unwrap_or(true),
+        devices: parse_devices(config.from_file.modbus.devices),
+        r#static: config
           .from_file
           .modbus
-          .devices
+          .r#static
           .into_iter()
-          .map(|(kind, device)| {
-            (
-              kind.clone(),
-              Device {
-                kind,
-                id: device
-                  .id
-                  .into_iter()
-                  .map(file::to_modbus_id_register)
-                  .collect(),
-                detect: device
-                  .detect
-                  .into_iter()
-                  .map(file::to_modbus_detect_register)
-                  .collect(),
-                measurement: device
-                  .measurement
-                  .into_iter()
-                  .map(file::to_modbus_measurement_register)
-                  .collect(),
-                configuration: device
-                  .configuration
-                  .into_iter()
-                  .map(file::to_modbus_value_register)
-                  .collect(),
-                daily: device
-                  .daily
-                  .into_iter()
-                  .map(file::to_modbus_value_register)
-                  .collect(),
-                nightly: device
-                  .nightly
-                  .into_iter()
-                  .map(file::to_modbus_value_register)
-                  .collect(),
-                time: device.time.map(|time| match time {
-                  file::TimeImplementation::SchneideriEM3xxx => {
-                    modbus::TimeImplementation::SchneideriEM3xxx
-                  }
-                }),
-              },
-            )
+          .filter_map(|device| {
+            file::to_modbus_static_device(device, &network, serial_line)
           })
-          .collect::<HashMap<_, _>>(),
+          .collect(),
       },
+      network,
     }
   }
 
@@ -395,6 +622,7 @@ impl Manager {
     let from_args = args::parse();
     let from_env = env::parse()?;
     let from_file = file::parse_file(from_args.config.as_deref()).await?;
+    check(&from_file)?;
 
     Ok(Unparsed {
       from_args,
@@ -403,3 +631,380 @@ impl Manager {
     })
   }
 }
+
+pub(crate) fn command() -> Option<Command> {
+  args::parse().command
+}
+
+// NOTE: serial ports keep their configured line unless the baud rate
+// is overridden
+pub(crate) fn command_destination(
+  config: &Values,
+  destination: CommandDestination,
+) -> Option<modbus::Destination> {
+  let line = destination
+    .path
+    .as_ref()
+    .and_then(|path| config.serial.ports.get(path).copied())
+    .unwrap_or(config.serial.line);
+
+  file::to_modbus_destination(
+    file::Destination {
+      transport: destination.transport.map(|transport| match transport {
+        args::Transport::Tcp => file::Transport::Tcp,
+        args::Transport::RtuOverTcp => file::Transport::RtuOverTcp,
+        args::Transport::Udp => file::Transport::Udp,
+        args::Transport::Rtu => file::Transport::Rtu,
+      }),
+      address: destination.address,
+      path: destination.path,
+      line: destination.baud_rate.map(|baud_rate| file::SerialLine {
+        baud_rate: Some(baud_rate),
+        ..Default::default()
+      }),
+      slave: destination.slave,
+    },
+    &config.network,
+    line,
+  )
+}
+
+pub(crate) fn schema() -> schemars::Schema {
+  schemars::schema_for!(file::Values)
+}
+
+// NOTE: like the simulator validation doesn't need the env
+pub(crate) async fn validation() -> Result<(), ReadError> {
+  let from_args = args::parse();
+  let from_file = file::parse_file(from_args.config.as_deref()).await?;
+
+  check(&from_file)
+}
+
+// NOTE: changes are reported down to device kinds and anything deeper
+// is reported on its closest ancestor
+const CHANGE_DEPTH: usize = 3;
+
+// NOTE: configs that can't be compared are reported as changed everywhere
+// so that nothing stale sticks around
+fn log_changes(old: &file::Values, new: &file::Values) -> Vec<String> {
+  let (old, new) = match (serde_json::to_value(old), serde_json::to_value(new))
+  {
+    (Ok(old), Ok(new)) => (old, new),
+    (Err(error), _) | (_, Err(error)) => {
+      tracing::warn!("Failed comparing configs {}", error);
+      return vec![String::new()];
+    }
+  };
+
+  let changes = changes("", &old, &new, CHANGE_DEPTH);
+  if changes.is_empty() {
+    tracing::info!("Config unchanged");
+  } else {
+    tracing::info!("Config changed in {}", changes.join(", "));
+  }
+
+  changes
+}
+
+// NOTE: an empty path stands for the whole config
+pub(crate) fn changed(changes: &[String], section: &str) -> bool {
+  changes.iter().any(|path| {
+    path.is_empty() || is_within(path, section) || is_within(section, path)
+  })
+}
+
+fn is_within(path: &str, ancestor: &str) -> bool {
+  path
+    .strip_prefix(ancestor)
+    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+fn changes(
+  path: &str,
+  old: &serde_json::Value,
+  new: &serde_json::Value,
+  depth: usize,
+) -> Vec<String> {
+  if old == new {
+    return Vec::new();
+  }
+
+  match (old, new) {
+    (serde_json::Value::Object(old), serde_json::Value::Object(new))
+      if depth > 0 =>
+    {
+      old
+        .keys()
+        .chain(new.keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .flat_map(|key| {
+          let path = if path.is_empty() {
+            key.clone()
+          } else {
+            format!("{path}.{key}")
+          };
+          changes(
+            &path,
+            old.get(key).unwrap_or(&serde_json::Value::Null),
+            new.get(key).unwrap_or(&serde_json::Value::Null),
+            depth.saturating_sub(1),
+          )
+        })
+        .collect()
+    }
+    _ => vec![path.to_string()],
+  }
+}
+
+fn check(from_file: &file::Values) -> Result<(), ReadError> {
+  validate::warn(from_file);
+  let issues = validate::validate(from_file);
+  if issues.is_empty() {
+    return Ok(());
+  }
+
+  for issue in &issues {
+    tracing::error!("{}", issue);
+  }
+
+  Err(file::ParseError::Invalid(issues).into())
+}
+
+// NOTE: the simulator doesn't need the env so it reads the file by itself
+pub(crate) async fn simulation() -> Result<Simulation, ReadError> {
+  let from_args = args::parse();
+  let from_file = file::parse_file(from_args.config.as_deref()).await?;
+
+  Ok(Simulation {
+    log_level: parse_log_level(&from_args, &from_file),
+    devices: parse_devices(from_file.modbus.devices),
+    servers: from_file
+      .simulator
+      .servers
+      .into_iter()
+      .enumerate()
+      .map(|(index, server)| parse_simulator_server(index, server))
+      .collect(),
+  })
+}
+
+fn parse_log_level(
+  from_args: &args::Values,
+  from_file: &file::Values,
+) -> tracing::level_filters::LevelFilter {
+  if from_args.trace {
+    tracing::level_filters::LevelFilter::TRACE
+  } else if from_args.debug {
+    tracing::level_filters::LevelFilter::DEBUG
+  } else if let Some(log_level) = &from_file.log_level {
+    match log_level {
+      file::LogLevel::Trace => tracing::level_filters::LevelFilter::TRACE,
+      file::LogLevel::Debug => tracing::level_filters::LevelFilter::DEBUG,
+      file::LogLevel::Info => tracing::level_filters::LevelFilter::INFO,
+      file::LogLevel::Warn => tracing::level_filters::LevelFilter::WARN,
+      file::LogLevel::Error => tracing::level_filters::LevelFilter::ERROR,
+    }
+  } else {
+    #[cfg(debug_assertions)]
+    {
+      tracing::level_filters::LevelFilter::DEBUG
+    }
+    #[cfg(not(debug_assertions))]
+    {
+      tracing::level_filters::LevelFilter::INFO
+    }
+  }
+}
+
+fn parse_devices(
+  devices: HashMap<String, file::Device>,
+) -> HashMap<String, Device> {
+  file::resolve_device_extensions(devices)
+    .into_iter()
+    .map(|(kind, device)| {
+      (
+        kind.clone(),
+        Device {
+          kind,
+          priority: device.priority.unwrap_or(0),
+          max_batch_quantity: device.max_batch_quantity,
+          turnaround_delay: device
+            .turnaround_delay
+            .map(file::milliseconds_to_chrono),
+          id: device
+            .id
+            .into_iter()
+            .map(file::to_modbus_id_register)
+            .collect(),
+          detect: device
+            .detect
+            .into_iter()
+            .map(file::to_modbus_detect_register)
+            .chain(
+              device
+                .identification
+                .into_iter()
+                .flat_map(file::to_modbus_identification_registers),
+            )
+            .collect(),
+          alternatives: device
+            .alternatives
+            .into_iter()
+            .map(|alternative| {
+              alternative
+                .detect
+                .into_iter()
+                .map(file::to_modbus_detect_register)
+                .collect()
+            })
+            .collect(),
+          measurement: device
+            .measurement
+            .into_iter()
+            .chain(device.blocks.into_iter().flat_map(expand_block))
+            .map(file::to_modbus_measurement_register)
+            .collect(),
+          groups: device
+            .groups
+            .into_iter()
+            .map(|group| MeasurementGroup {
+              name: group.name,
+              interval: file::milliseconds_to_chrono(group.interval),
+              measurement: group
+                .measurement
+                .into_iter()
+                .map(file::to_modbus_measurement_register)
+                .collect(),
+            })
+            .collect(),
+          configuration: device
+            .configuration
+            .into_iter()
+            .map(file::to_modbus_value_register)
+            .collect(),
+          daily: device
+            .daily
+            .into_iter()
+            .map(file::to_modbus_value_register)
+            .collect(),
+          nightly: device
+            .nightly
+            .into_iter()
+            .map(file::to_modbus_value_register)
+            .collect(),
+          time: device.time.map(|time| match time {
+            file::TimeImplementation::SchneideriEM3xxx => {
+              modbus::TimeImplementation::SchneideriEM3xxx
+            }
+          }),
+        },
+      )
+    })
+    .collect::<HashMap<_, _>>()
+}
+
+const BLOCK_NUMBER_TEMPLATE: &str = "{n}";
+
+fn expand_block(
+  block: file::MeasurementBlock,
+) -> Vec<file::MeasurementRegister> {
+  let start = block.start.unwrap_or(1);
+  (0..block.count)
+    .flat_map(|repetition| {
+      let base = repetition
+        .checked_mul(block.stride)
+        .and_then(|offset| offset.checked_add(block.address));
+      let number = start.saturating_add(repetition);
+      block.measurement.iter().filter_map(move |register| {
+        let address = base.and_then(|base| base.checked_add(register.address));
+        let name = register
+          .name
+          .replace(BLOCK_NUMBER_TEMPLATE, number.to_string().as_str());
+        match address {
+          Some(address) => Some(file::MeasurementRegister {
+            name,
+            address,
+            ..register.clone()
+          }),
+          None => {
+            tracing::warn!("Block register {} is out of address range", name);
+            None
+          }
+        }
+      })
+    })
+    .collect()
+}
+
+// NOTE: default slaves and ids are derived from positions
+// so that every simulated device gets a distinct id
+fn parse_simulator_server(
+  index: usize,
+  server: file::SimulatorServer,
+) -> SimulatorServer {
+  SimulatorServer {
+    transport: match server.transport {
+      None | Some(file::SimulatorTransport::Tcp) => {
+        SimulatorTransport::Tcp(file::make_socket_address(
+          server.address,
+          ([127, 0, 0, 1], 5020).into(),
+        ))
+      }
+      Some(file::SimulatorTransport::Rtu) => {
+        SimulatorTransport::Rtu(server.link)
+      }
+    },
+    devices: server
+      .devices
+      .into_iter()
+      .enumerate()
+      .map(|(position, device)| {
+        let slave = device.slave.unwrap_or_else(|| {
+          u8::try_from(position).unwrap_or(u8::MAX).saturating_add(1)
+        });
+        SimulatorDevice {
+          kind: device.kind,
+          slave,
+          id: device.id.unwrap_or_else(|| {
+            u64::try_from(index)
+              .unwrap_or(u64::MAX)
+              .saturating_mul(256)
+              .saturating_add(u64::from(slave))
+          }),
+          detect: device.detect,
+          values: device
+            .values
+            .into_iter()
+            .map(|(name, value)| (name, file::to_simulator_value(value)))
+            .collect(),
+          faults: SimulatorFaults {
+            timeout: file::to_probability(device.faults.timeout),
+            exception: file::to_probability(device.faults.exception),
+            wrong_slave: file::to_probability(device.faults.wrong_slave),
+          },
+        }
+      })
+      .collect(),
+  }
+}
+
+// NOTE: 19200 8E1 is the modbus rtu default and the rest are common in the wild
+fn default_serial_candidates() -> Vec<modbus::connection::Line> {
+  [
+    (19200, tokio_serial::Parity::Even),
+    (9600, tokio_serial::Parity::Even),
+    (9600, tokio_serial::Parity::None),
+    (19200, tokio_serial::Parity::None),
+    (38400, tokio_serial::Parity::None),
+    (115200, tokio_serial::Parity::None),
+  ]
+  .into_iter()
+  .map(|(baud_rate, parity)| modbus::connection::Line {
+    baud_rate,
+    parity,
+    ..modbus::connection::Line::default()
+  })
+  .collect()
+}
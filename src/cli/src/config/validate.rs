@@ -0,0 +1,475 @@
+use std::{collections::HashMap, str::FromStr};
+
+use thiserror::Error;
+use tokio_modbus::{Address, Quantity};
+
+use super::{file, Device};
+use crate::service::modbus::{self, batch, span::Function, RegisterStorage};
+
+// NOTE: parsing stays lenient so a running daemon keeps going with
+// defaults but these are the mistakes that would otherwise go unnoticed
+
+#[derive(Debug, Clone, Error)]
+pub(crate) enum Issue {
+  #[error(
+    "Schedule {name} has invalid cron expression {expression:?} {error}"
+  )]
+  InvalidCron {
+    name: &'static str,
+    expression: String,
+    error: String,
+  },
+
+  #[error("Device {kind} has invalid regex {pattern:?} {error}")]
+  InvalidRegex {
+    kind: String,
+    pattern: String,
+    error: String,
+  },
+
+  #[error(
+    "Device {kind} has overlapping measurement registers {first} and {second}"
+  )]
+  OverlappingRegisters {
+    kind: String,
+    first: String,
+    second: String,
+  },
+
+  #[error("Device {kind} has duplicate measurement register {name}")]
+  DuplicateName { kind: String, name: String },
+
+  #[error("Device {kind} register at {address} with quantity {quantity} exceeds the batch limit {limit}")]
+  ExceedsBatchLimit {
+    kind: String,
+    address: Address,
+    quantity: Quantity,
+    limit: Quantity,
+  },
+
+  #[error("Device {kind} register at {address} with quantity {quantity} exceeds the address space")]
+  ExceedsAddressSpace {
+    kind: String,
+    address: Address,
+    quantity: Quantity,
+  },
+
+  #[error("Device {kind} {group} value register at {address} has impossible length {length}")]
+  ImpossibleLength {
+    kind: String,
+    group: &'static str,
+    address: Address,
+    length: usize,
+  },
+
+  #[error("Gateway is enabled without any configured units")]
+  GatewayWithoutUnits,
+
+  #[error("Gateway unit {unit} of {id} is not a valid modbus unit")]
+  InvalidGatewayUnit { id: String, unit: u8 },
+
+  #[error("Gateway unit {unit} is configured for both {first} and {second}")]
+  DuplicateGatewayUnit {
+    unit: u8,
+    first: String,
+    second: String,
+  },
+}
+
+pub(crate) fn validate(values: &file::Values) -> Vec<Issue> {
+  let mut issues = Vec::new();
+  validate_schedule(&values.schedule, &mut issues);
+  validate_gateway(&values.gateway, &mut issues);
+
+  let mut kinds = values.modbus.devices.keys().collect::<Vec<_>>();
+  kinds.sort();
+  for kind in kinds {
+    if let Some(device) = values.modbus.devices.get(kind) {
+      validate_regexes(kind, device, &mut issues);
+    }
+  }
+
+  let devices = super::parse_devices(values.modbus.devices.clone());
+  let mut devices = devices.values().collect::<Vec<_>>();
+  devices.sort_by(|device, other| device.kind.cmp(&other.kind));
+  for device in devices {
+    validate_measurement(device, &mut issues);
+    validate_quantities(device, &mut issues);
+    validate_values(device, &mut issues);
+  }
+
+  issues
+}
+
+// NOTE: kinds detected through identification still work over tcp
+// so they are only worth a warning and not a config error
+pub(crate) fn warn(values: &file::Values) {
+  let devices = super::parse_devices(values.modbus.devices.clone());
+  let mut kinds = devices
+    .values()
+    .filter(|device| {
+      device.alternatives.is_empty()
+        && device
+          .detect
+          .iter()
+          .any(|register| register.function == Function::DeviceIdentification)
+    })
+    .map(|device| device.kind.as_str())
+    .collect::<Vec<_>>();
+  kinds.sort();
+  for kind in kinds {
+    tracing::warn!(
+      "Device {} is only detected through identification \
+        which rtu devices can't be asked for",
+      kind
+    );
+  }
+}
+
+fn validate_schedule(schedule: &file::Schedule, issues: &mut Vec<Issue>) {
+  let expressions = [
+    ("discover", &schedule.discover),
+    ("ping", &schedule.ping),
+    ("measure", &schedule.measure),
+    ("push", &schedule.push),
+    ("update", &schedule.update),
+    ("health", &schedule.health),
+    ("daily", &schedule.daily),
+    ("nightly", &schedule.nightly),
+    ("time", &schedule.time),
+    ("poll", &schedule.poll),
+  ];
+  for (name, expression) in expressions {
+    let Some(expression) = expression else {
+      continue;
+    };
+    if let Err(error) = cron::Schedule::from_str(expression) {
+      issues.push(Issue::InvalidCron {
+        name,
+        expression: expression.clone(),
+        error: error.to_string(),
+      });
+    }
+  }
+}
+
+fn validate_gateway(gateway: &file::Gateway, issues: &mut Vec<Issue>) {
+  if !gateway.enabled.unwrap_or(false) {
+    return;
+  }
+
+  let mut units = gateway.units.iter().flatten().collect::<Vec<_>>();
+  if units.is_empty() {
+    issues.push(Issue::GatewayWithoutUnits);
+    return;
+  }
+
+  units.sort_by(|(id, unit), (other_id, other_unit)| {
+    unit.cmp(other_unit).then(id.cmp(other_id))
+  });
+  let mut previous = None::<(&String, &u8)>;
+  for (id, unit) in units {
+    if !(1..=247).contains(unit) {
+      issues.push(Issue::InvalidGatewayUnit {
+        id: id.clone(),
+        unit: *unit,
+      });
+    }
+    if let Some((first, previous)) = previous {
+      if previous == unit {
+        issues.push(Issue::DuplicateGatewayUnit {
+          unit: *unit,
+          first: first.clone(),
+          second: id.clone(),
+        });
+      }
+    }
+    previous = Some((id, unit));
+  }
+}
+
+fn validate_regexes(
+  kind: &str,
+  device: &file::Device,
+  issues: &mut Vec<Issue>,
+) {
+  let identification = device
+    .identification
+    .iter()
+    .flat_map(|identification| {
+      [
+        &identification.vendor,
+        &identification.product,
+        &identification.revision,
+      ]
+    })
+    .flatten();
+  let patterns = device
+    .detect
+    .iter()
+    .chain(
+      device
+        .alternatives
+        .iter()
+        .flat_map(|alternative| alternative.detect.iter()),
+    )
+    .map(|register| &register.r#match)
+    .chain(identification);
+
+  for pattern in patterns {
+    if let Err(error) = regex::Regex::new(pattern) {
+      // NOTE: syntax errors draw the pattern over multiple lines
+      // and end with the actual reason
+      let error = error.to_string();
+      issues.push(Issue::InvalidRegex {
+        kind: kind.to_string(),
+        pattern: pattern.clone(),
+        error: error.lines().last().unwrap_or_default().to_string(),
+      });
+    }
+  }
+}
+
+// NOTE: identification addresses are object ids so they can't overlap
+fn validate_measurement(device: &Device, issues: &mut Vec<Issue>) {
+  let mut names = HashMap::new();
+  for register in &device.measurement {
+    let count = names.entry(register.name.as_str()).or_insert(0usize);
+    if *count == 1 {
+      issues.push(Issue::DuplicateName {
+        kind: device.kind.clone(),
+        name: register.name.clone(),
+      });
+    }
+    *count = count.saturating_add(1);
+  }
+
+  let mut registers = device
+    .measurement
+    .iter()
+    .filter(|register| register.function != Function::DeviceIdentification)
+    .map(|register| {
+      (
+        register.function,
+        u32::from(register.address),
+        u32::from(register.address)
+          .saturating_add(u32::from(register.storage.quantity())),
+        register.name.as_str(),
+      )
+    })
+    .collect::<Vec<_>>();
+  registers.sort_by_key(|(function, start, _, _)| (*function, *start));
+
+  let mut previous: Option<(Function, u32, &str)> = None;
+  for (function, start, end, name) in registers {
+    match previous {
+      Some((previous_function, previous_end, previous_name))
+        if previous_function == function && start < previous_end =>
+      {
+        issues.push(Issue::OverlappingRegisters {
+          kind: device.kind.clone(),
+          first: previous_name.to_string(),
+          second: name.to_string(),
+        });
+        if end > previous_end {
+          previous = Some((function, end, name));
+        }
+      }
+      _ => previous = Some((function, end, name)),
+    }
+  }
+}
+
+fn validate_quantities(device: &Device, issues: &mut Vec<Issue>) {
+  let spans = device
+    .id
+    .iter()
+    .map(|register| (register.function, register.address, &register.storage))
+    .chain(
+      device
+        .detect
+        .iter()
+        .chain(device.alternatives.iter().flatten())
+        .map(|register| {
+          (register.function, register.address, &register.storage)
+        }),
+    )
+    .chain(device.measurement.iter().map(|register| {
+      (register.function, register.address, &register.storage)
+    }));
+
+  for (function, address, storage) in spans {
+    let quantity = storage.quantity();
+    // NOTE: identification objects are requested one by one
+    // so batching never caps them
+    let limit = batch::max_quantity_for(function);
+    let limit = match device.max_batch_quantity {
+      Some(max_batch_quantity)
+        if function != Function::DeviceIdentification =>
+      {
+        limit.min(max_batch_quantity)
+      }
+      _ => limit,
+    };
+    if quantity > limit {
+      issues.push(Issue::ExceedsBatchLimit {
+        kind: device.kind.clone(),
+        address,
+        quantity,
+        limit,
+      });
+    }
+    if function != Function::DeviceIdentification
+      && address.checked_add(quantity.saturating_sub(1)).is_none()
+    {
+      issues.push(Issue::ExceedsAddressSpace {
+        kind: device.kind.clone(),
+        address,
+        quantity,
+      });
+    }
+  }
+}
+
+fn validate_values(device: &Device, issues: &mut Vec<Issue>) {
+  let groups = [
+    ("configuration", &device.configuration),
+    ("daily", &device.daily),
+    ("nightly", &device.nightly),
+  ];
+  for (group, registers) in groups {
+    for register in registers {
+      // NOTE: strings are written two bytes per register
+      let length = match &register.storage {
+        modbus::RegisterValueStorage::Raw(storage) => storage.value.len(),
+        modbus::RegisterValueStorage::String(storage) => {
+          storage.value.len().div_ceil(2)
+        }
+        storage => usize::from(storage.quantity()),
+      };
+      let fits = Quantity::try_from(length).is_ok_and(|quantity| {
+        quantity > 0
+          && quantity <= batch::MAX_WRITE_REGISTER_QUANTITY
+          && register
+            .address
+            .checked_add(quantity.saturating_sub(1))
+            .is_some()
+      });
+      if !fits {
+        issues.push(Issue::ImpossibleLength {
+          kind: device.kind.clone(),
+          group,
+          address: register.address,
+          length,
+        });
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[allow(clippy::unwrap_used, reason = "test configs are valid toml")]
+  fn issues(raw: &str) -> Vec<Issue> {
+    validate(&toml::from_str::<file::Values>(raw).unwrap())
+  }
+
+  #[test]
+  fn bundled_config_is_valid() {
+    let issues = issues(include_str!("../../../../assets/pidgeon/config.toml"));
+    assert!(issues.is_empty(), "{issues:?}");
+  }
+
+  #[test]
+  fn overlapping_registers_are_reported() {
+    let issues = issues(
+      r#"
+        [[modbus.devices.meter.measurement]]
+        name = "power"
+        address = 10
+        kind = { u32 = {} }
+
+        [[modbus.devices.meter.measurement]]
+        name = "energy"
+        address = 11
+        kind = { u16 = {} }
+
+        [[modbus.devices.meter.measurement]]
+        name = "current"
+        address = 11
+        function = "input"
+        kind = { u16 = {} }
+      "#,
+    );
+
+    assert!(
+      matches!(
+        issues.as_slice(),
+        [Issue::OverlappingRegisters { kind, first, second }]
+          if kind == "meter" && first == "power" && second == "energy"
+      ),
+      "{issues:?}"
+    );
+  }
+
+  #[test]
+  fn registers_over_the_batch_limit_are_reported() {
+    let issues = issues(
+      r#"
+        [modbus.devices.meter]
+        max_batch_quantity = 2
+
+        [[modbus.devices.meter.measurement]]
+        name = "power"
+        address = 10
+        kind = { u32 = {} }
+
+        [[modbus.devices.meter.measurement]]
+        name = "energy"
+        address = 20
+        kind = { u64 = {} }
+      "#,
+    );
+
+    assert!(
+      matches!(
+        issues.as_slice(),
+        [Issue::ExceedsBatchLimit {
+          address: 20,
+          quantity: 4,
+          limit: 2,
+          ..
+        }]
+      ),
+      "{issues:?}"
+    );
+  }
+
+  #[test]
+  fn duplicate_gateway_units_are_reported() {
+    let issues = issues(
+      r#"
+        [gateway]
+        enabled = true
+
+        [gateway.units]
+        first = 1
+        second = 1
+        third = 0
+      "#,
+    );
+
+    assert!(
+      matches!(
+        issues.as_slice(),
+        [
+          Issue::InvalidGatewayUnit { unit: 0, .. },
+          Issue::DuplicateGatewayUnit { unit: 1, first, second },
+        ] if first == "first" && second == "second"
+      ),
+      "{issues:?}"
+    );
+  }
+}
@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+
+use notify::Watcher;
+use thiserror::Error;
+use tokio::signal::unix::{signal, SignalKind};
+
+use super::{file, Manager};
+
+// NOTE: editors usually save by replacing the file so the directory gets
+// watched and bursts of events get debounced into a single reload
+const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub(crate) enum WatchError {
+  #[error("Failed locating config file")]
+  Location(#[from] file::ParseError),
+
+  #[error("Failed watching config file")]
+  Notify(#[from] notify::Error),
+
+  #[error("Failed listening for hangup")]
+  Signal(#[from] std::io::Error),
+}
+
+impl Manager {
+  // NOTE: library files are not watched so a hangup is needed to pick up
+  // changes there
+  pub(crate) async fn watch(
+    &self,
+  ) -> Result<tokio::task::JoinHandle<()>, WatchError> {
+    let location = {
+      let values = self.lock.lock().await;
+      file::location(values.from_args.config.as_deref())?
+    };
+    let directory = match location.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+      _ => PathBuf::from("."),
+    };
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(
+      move |event: notify::Result<notify::Event>| {
+        let _ = sender.send(event);
+      },
+    )?;
+    watcher.watch(&directory, notify::RecursiveMode::NonRecursive)?;
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    let manager = self.clone();
+    Ok(tokio::spawn(async move {
+      let _watcher = watcher;
+      loop {
+        tokio::select! {
+          event = receiver.recv() => match event {
+            None => break,
+            Some(Err(error)) => {
+              tracing::warn!("Failed watching config file {}", error);
+              continue;
+            }
+            Some(Ok(event)) if !is_change(&event, &location) => continue,
+            Some(Ok(_)) => {
+              tokio::time::sleep(DEBOUNCE).await;
+              while receiver.try_recv().is_ok() {}
+              tracing::info!("Reloading changed config file");
+            }
+          },
+          Some(()) = hangup.recv() => {
+            tracing::info!("Reloading config file on hangup");
+          }
+        }
+
+        manager.reload().await;
+      }
+    }))
+  }
+}
+
+fn is_change(event: &notify::Event, location: &Path) -> bool {
+  let modifies = matches!(
+    event.kind,
+    notify::EventKind::Create(_)
+      | notify::EventKind::Modify(_)
+      | notify::EventKind::Remove(_)
+  );
+
+  modifies
+    && event
+      .paths
+      .iter()
+      .any(|path| path.file_name() == location.file_name())
+}
@@ -0,0 +1,264 @@
+use std::io::Write;
+
+use crate::service::modbus::connection::Device;
+use crate::{config, process, service, service::modbus};
+
+// NOTE: diagnostics go through the same services and device configs as the
+// daemon so technicians see exactly what the daemon would read
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RunError {
+  #[error("Invalid destination")]
+  InvalidDestination,
+
+  #[error("Unknown device kind {0}")]
+  UnknownKind(String),
+
+  #[error("No sunspec models found")]
+  NoSunspec,
+
+  #[error("Unknown registers {0:?}")]
+  UnknownRegisters(Vec<String>),
+
+  #[error("Failed reading registers")]
+  Read(#[from] modbus::ServerReadError),
+
+  #[error("Failed writing registers")]
+  Write(#[from] modbus::ServerWriteError),
+
+  #[error("Failed writing output")]
+  Output(#[from] std::io::Error),
+
+  #[error("Failed serializing output")]
+  Serialize(#[from] serde_json::Error),
+}
+
+pub(crate) async fn run(
+  manager: config::Manager,
+  command: config::Diagnostic,
+) -> anyhow::Result<()> {
+  let config = manager.values().await;
+  let services = service::Container::new(config.clone());
+
+  let output = match command {
+    config::Diagnostic::Read {
+      destination,
+      kind,
+      register,
+    } => {
+      let destination = to_destination(&config, destination)?;
+      let device = if kind == modbus::sunspec::KIND {
+        sunspec_device(manager, services.clone(), destination.clone()).await?
+      } else {
+        config
+          .modbus
+          .devices
+          .values()
+          .find(|device| device.kind == kind)
+          .cloned()
+          .ok_or(RunError::UnknownKind(kind))?
+      };
+      read(&services, destination, &device, register).await?
+    }
+    config::Diagnostic::Write {
+      destination,
+      register,
+      values,
+    } => {
+      let destination = to_destination(&config, destination)?;
+      write(&services, destination, register, values).await?
+    }
+    config::Diagnostic::Detect { destination } => {
+      let destination = to_destination(&config, destination)?;
+      let discover = process::discover::Process::new(manager, services);
+      serde_json::Value::Array(
+        discover
+          .detect(&config, destination)
+          .await
+          .iter()
+          .map(device_match_json)
+          .collect(),
+      )
+    }
+    config::Diagnostic::Scan => {
+      let discover = process::discover::Process::new(manager, services);
+      serde_json::Value::Array(
+        discover
+          .scan(&config)
+          .await
+          .iter()
+          .map(device_match_json)
+          .collect(),
+      )
+    }
+  };
+
+  let mut stdout = std::io::stdout().lock();
+  serde_json::to_writer_pretty(&mut stdout, &output).map_err(RunError::from)?;
+  writeln!(stdout).map_err(RunError::from)?;
+
+  Ok(())
+}
+
+fn to_destination(
+  config: &config::Values,
+  destination: config::CommandDestination,
+) -> Result<modbus::Destination, RunError> {
+  config::command_destination(config, destination)
+    .ok_or(RunError::InvalidDestination)
+}
+
+// NOTE: sunspec devices have no configured kind so their models
+// are walked like discovery does
+async fn sunspec_device(
+  manager: config::Manager,
+  services: service::Container,
+  destination: modbus::Destination,
+) -> Result<config::Device, RunError> {
+  process::discover::Process::new(manager, services)
+    .match_sunspec(destination)
+    .await
+    .map(|(device, _)| device)
+    .ok_or(RunError::NoSunspec)
+}
+
+async fn read(
+  services: &service::Container,
+  destination: modbus::Destination,
+  device: &config::Device,
+  names: Vec<String>,
+) -> Result<serde_json::Value, RunError> {
+  let unknown = names
+    .iter()
+    .filter(|name| {
+      !device
+        .measurement
+        .iter()
+        .any(|register| &register.name == *name)
+    })
+    .cloned()
+    .collect::<Vec<_>>();
+  if !unknown.is_empty() {
+    return Err(RunError::UnknownRegisters(unknown));
+  }
+
+  let id_registers = services
+    .modbus()
+    .read_from_destination(
+      destination.clone(),
+      device.id.clone(),
+      device.max_batch_quantity,
+    )
+    .await?;
+
+  let selected = device
+    .measurement
+    .iter()
+    .filter(|register| names.is_empty() || names.contains(&register.name))
+    .cloned()
+    .collect::<Vec<_>>();
+  // NOTE: scale registers are read even when not asked for
+  // so that the registers asked for come out scaled
+  let scale_registers = modbus::referenced_scale_registers(
+    &selected,
+    device.measurement.iter().chain(
+      device
+        .groups
+        .iter()
+        .flat_map(|group| group.measurement.iter()),
+    ),
+  );
+
+  let mut registers = services
+    .modbus()
+    .read_from_destination(
+      destination,
+      selected
+        .iter()
+        .chain(scale_registers.iter())
+        .cloned()
+        .collect::<Vec<_>>(),
+      device.max_batch_quantity,
+    )
+    .await?;
+  modbus::apply_scale_registers(&selected, &mut registers);
+  registers.retain(|register| {
+    selected
+      .iter()
+      .any(|selected| selected.name == register.name)
+  });
+
+  Ok(serde_json::json!({
+    "id": modbus::make_id(device.kind.clone(), id_registers),
+    "kind": device.kind,
+    "measurement": modbus::serialize_registers(registers),
+  }))
+}
+
+async fn write(
+  services: &service::Container,
+  destination: modbus::Destination,
+  register: u16,
+  values: Vec<u16>,
+) -> Result<serde_json::Value, RunError> {
+  let quantity = values.len();
+  let timestamps = services
+    .modbus()
+    .write_to_destination(
+      destination,
+      vec![modbus::record::SimpleRecord {
+        address: register,
+        values,
+      }],
+    )
+    .await?;
+
+  Ok(serde_json::json!({
+    "register": register,
+    "quantity": quantity,
+    "timestamp": timestamps.first(),
+  }))
+}
+
+fn device_match_json(
+  device_match: &process::discover::DeviceMatch,
+) -> serde_json::Value {
+  serde_json::json!({
+    "id": device_match.id,
+    "kind": device_match.kind,
+    "destination": destination_json(&device_match.destination),
+    "sunspec": device_match.sunspec,
+    "identification": device_match.identification,
+  })
+}
+
+fn destination_json(destination: &modbus::Destination) -> serde_json::Value {
+  let mut value = match &destination.device {
+    Device::Tcp(address) => network_json("tcp", address),
+    Device::RtuOverTcp(address) => network_json("rtu-over-tcp", address),
+    Device::Udp(address) => network_json("udp", address),
+    Device::Rtu { path, line } => serde_json::json!({
+      "transport": "rtu",
+      "path": path,
+      "baudRate": line.baud_rate,
+      "parity": format!("{:?}", line.parity),
+      "stopBits": format!("{:?}", line.stop_bits),
+      "dataBits": format!("{:?}", line.data_bits),
+    }),
+  };
+  if let serde_json::Value::Object(object) = &mut value {
+    object.insert("slave".to_string(), serde_json::json!(destination.slave));
+  }
+
+  value
+}
+
+fn network_json(
+  transport: &str,
+  address: &std::net::SocketAddr,
+) -> serde_json::Value {
+  serde_json::json!({
+    "transport": transport,
+    "address": address.to_string(),
+  })
+}
@@ -8,14 +8,18 @@
 #![allow(dead_code, reason = "remove once the time stuff is complete")]
 
 mod config;
+mod diagnostic;
 mod process;
 mod service;
+mod simulator;
 
 use std::fmt::Debug;
+use std::io::Write;
 
 use futures_time::future::FutureExt;
 use tracing_subscriber::{
-  layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+  fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt,
+  EnvFilter,
 };
 
 // TODO: configurable timeouts
@@ -23,7 +27,15 @@ use tracing_subscriber::{
 #[tokio::main]
 #[tracing::instrument]
 async fn main() -> anyhow::Result<()> {
-  let format_layer = tracing_subscriber::fmt::layer();
+  // NOTE: diagnostics and the schema print json so logs go to stderr instead
+  let command = config::command();
+  let writer = match command {
+    Some(config::Command::Diagnostic(_) | config::Command::Schema) => {
+      BoxMakeWriter::new(std::io::stderr)
+    }
+    _ => BoxMakeWriter::new(std::io::stdout),
+  };
+  let format_layer = tracing_subscriber::fmt::layer().with_writer(writer);
   let (filter_layer, filter_handle) =
     tracing_subscriber::reload::Layer::new(build_tracing_filter("info")?);
   tracing_subscriber::registry()
@@ -31,7 +43,37 @@ async fn main() -> anyhow::Result<()> {
     .with(format_layer)
     .try_init()?;
 
-  let manager = config::Manager::new().await?; // NITPICK: handle this more appropriately
+  if let Some(config::Command::Simulate) = command {
+    let simulation = config::simulation().await?;
+    let log_level = simulation.log_level.to_string();
+    filter_handle.modify(move |filter| {
+      #[allow(clippy::unwrap_used, reason = "it should panic")]
+      let new_filter = build_tracing_filter(log_level.as_str()).unwrap();
+      *filter = new_filter;
+    })?;
+
+    return simulator::run(simulation).await;
+  }
+
+  if let Some(config::Command::Schema) = command {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &config::schema())?;
+    writeln!(stdout)?;
+    return Ok(());
+  }
+
+  if let Some(config::Command::Validate) = command {
+    config::validation().await?;
+    tracing::info!("Config is valid");
+    return Ok(());
+  }
+
+  let manager = match command {
+    Some(config::Command::Diagnostic(_)) => {
+      config::Manager::diagnostic().await?
+    }
+    _ => config::Manager::new().await?, // NITPICK: handle this more appropriately
+  };
   let config = manager.values().await;
 
   let log_level = config.log_level.to_string();
@@ -41,6 +83,10 @@ async fn main() -> anyhow::Result<()> {
     *filter = new_filter;
   })?;
 
+  if let Some(config::Command::Diagnostic(command)) = command {
+    return diagnostic::run(manager, command).await;
+  }
+
   let id = config.cloud.id.clone();
   tracing::info!("Starting {id}");
 
@@ -53,11 +99,24 @@ async fn main() -> anyhow::Result<()> {
     .timeout(futures_time::time::Duration::from_millis(60_000))
     .await??;
 
+  services.gateway().start().await?;
+
   processes.startup().await?;
 
+  let watcher = match manager.watch().await {
+    Ok(watcher) => Some(watcher),
+    Err(error) => {
+      tracing::warn!("Failed watching config {}", error);
+      None
+    }
+  };
+
   if let Err(error) = tokio::signal::ctrl_c().await {
     tracing::error!("Failed waiting for ctrlc signal {}", error);
   };
+  if let Some(watcher) = watcher {
+    watcher.abort();
+  }
   if let Err(error) = processes
     .shutdown()
     .timeout(futures_time::time::Duration::from_millis(60_000))
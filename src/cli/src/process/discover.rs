@@ -1,4 +1,4 @@
-use futures::future::join_all;
+use futures::{future::join_all, StreamExt};
 use futures_time::future::FutureExt;
 
 #[allow(unused_imports, reason = "services")]
@@ -12,6 +12,9 @@ pub(crate) struct Process {
 
   #[allow(dead_code, reason = "process")]
   services: service::Container,
+
+  // NOTE: kinds already reported as not identifiable over rtu
+  unidentifiable: std::sync::Mutex<std::collections::HashSet<String>>,
 }
 
 impl Process {
@@ -19,7 +22,11 @@ impl Process {
     config: config::Manager,
     services: service::Container,
   ) -> Self {
-    Self { config, services }
+    Self {
+      config,
+      services,
+      unidentifiable: std::sync::Mutex::new(std::collections::HashSet::new()),
+    }
   }
 }
 
@@ -31,49 +38,26 @@ impl super::Recurring for Process {
   async fn execute(&self) -> anyhow::Result<()> {
     let config = self.config.values().await;
 
-    let addresses = if !config.local {
-      self.services.net().scan_modbus().await
-    } else {
-      Vec::new()
-    };
-    let addresses_len = addresses.len();
-
-    let ports = self.services.serial().scan_modbus().await;
-    let ports_len = ports.len();
+    let static_len = self.bind_static(&config).await;
 
-    let device_matches = join_all(
-      addresses
-        .into_iter()
-        .map(|address| self.match_modbus_device(&config, Device::Tcp(address)))
-        .chain(ports.into_iter().map(|port| {
-          self.match_modbus_device(
-            &config,
-            Device::Rtu {
-              path: port.path.clone(),
-              baud_rate: port.baud_rate,
-            },
-          )
-        })),
-    )
-    .await
-    .into_iter()
-    .flatten()
-    .collect::<Vec<_>>();
+    let device_matches = self.scan(&config).await;
     let device_matches_len = device_matches.len();
 
-    let consolidated_matches = join_all(
+    let consolidated_matches = futures::stream::iter(
       device_matches
         .into_iter()
         .map(|device_match| self.consolidate(device_match)),
     )
+    .buffer_unordered(config.network.concurrency)
+    .collect::<Vec<_>>()
     .await;
     let consolidated_matches_len = consolidated_matches.len();
 
     tracing::info!(
-      "Scanned {:?} modbus servers with {:?} devices of which {:?} were consolidated",
-      addresses_len.saturating_add(ports_len),
+      "Consolidated {:?} of {:?} devices and bound {:?} static devices",
+      consolidated_matches_len,
       device_matches_len,
-      consolidated_matches_len
+      static_len
     );
 
     Ok(())
@@ -81,13 +65,154 @@ impl super::Recurring for Process {
 }
 
 #[derive(Debug, Clone)]
-struct DeviceMatch {
-  id: String,
-  kind: String,
-  destination: modbus::Destination,
+pub(crate) struct DeviceMatch {
+  pub(crate) id: String,
+  pub(crate) kind: String,
+  pub(crate) destination: modbus::Destination,
+  pub(crate) max_batch_quantity: Option<u16>,
+  pub(crate) turnaround_delay: Option<chrono::Duration>,
+  pub(crate) sunspec: Option<serde_json::Value>,
+  pub(crate) identification: Option<serde_json::Value>,
 }
 
 impl Process {
+  #[tracing::instrument(skip(self, config))]
+  pub(crate) async fn scan(&self, config: &config::Values) -> Vec<DeviceMatch> {
+    let (addresses, rtu_over_tcp_addresses, udp_addresses) = if !config.local {
+      (
+        self.services.net().scan_modbus().await,
+        self.services.net().scan_modbus_rtu_over_tcp().await,
+        self.services.net().scan_modbus_udp().await,
+      )
+    } else {
+      (Vec::new(), Vec::new(), Vec::new())
+    };
+    let addresses_len = addresses
+      .len()
+      .saturating_add(rtu_over_tcp_addresses.len())
+      .saturating_add(udp_addresses.len());
+
+    let ports = self.services.serial().scan_modbus().await;
+    let ports_len = ports.len();
+
+    // NOTE: udp addresses are not probed so big ranges would otherwise
+    // open a socket for every address at once
+    let device_matches = futures::stream::iter(
+      addresses
+        .into_iter()
+        .map(Device::Tcp)
+        .chain(rtu_over_tcp_addresses.into_iter().map(Device::RtuOverTcp))
+        .chain(udp_addresses.into_iter().map(Device::Udp))
+        .map(|modbus_device| self.match_modbus_device(config, modbus_device)),
+    )
+    .buffer_unordered(config.network.concurrency)
+    .collect::<Vec<_>>()
+    .await
+    .into_iter()
+    .chain(
+      futures::stream::iter(
+        ports
+          .into_iter()
+          .map(|port| self.match_serial_port(config, port)),
+      )
+      .buffer_unordered(config.network.concurrency)
+      .collect::<Vec<_>>()
+      .await,
+    )
+    .flatten()
+    .collect::<Vec<_>>();
+
+    tracing::info!(
+      "Scanned {:?} modbus servers with {:?} devices",
+      addresses_len.saturating_add(ports_len),
+      device_matches.len()
+    );
+
+    device_matches
+  }
+
+  // NOTE: destinations without a slave are matched like scanned servers
+  #[tracing::instrument(skip(self, config))]
+  pub(crate) async fn detect(
+    &self,
+    config: &config::Values,
+    destination: modbus::Destination,
+  ) -> Vec<DeviceMatch> {
+    match destination.slave {
+      Some(_) => self
+        .match_destination(config, destination)
+        .await
+        .into_iter()
+        .collect(),
+      None => self.match_modbus_device(config, destination.device).await,
+    }
+  }
+
+  // NOTE: static devices skip detection but their id is still read
+  // so that a swapped device doesn't take over the data of the old one
+  #[tracing::instrument(skip(self, config))]
+  pub(super) async fn bind_static(&self, config: &config::Values) -> usize {
+    let device_matches = futures::stream::iter(
+      config
+        .modbus
+        .r#static
+        .clone()
+        .into_iter()
+        .map(|device| async move { self.match_static(config, &device).await }),
+    )
+    .buffer_unordered(config.network.concurrency)
+    .collect::<Vec<_>>()
+    .await;
+
+    futures::stream::iter(
+      device_matches
+        .into_iter()
+        .flatten()
+        .map(|device_match| self.consolidate(device_match)),
+    )
+    .buffer_unordered(config.network.concurrency)
+    .collect::<Vec<_>>()
+    .await
+    .into_iter()
+    .flatten()
+    .count()
+  }
+
+  #[tracing::instrument(skip(self, config))]
+  async fn match_static(
+    &self,
+    config: &config::Values,
+    device: &config::StaticDevice,
+  ) -> Option<DeviceMatch> {
+    let Some(device_config) = config
+      .modbus
+      .devices
+      .values()
+      .find(|device_config| device_config.kind == device.kind)
+      .cloned()
+    else {
+      tracing::warn!("Static device kind {} not configured", device.kind);
+      return None;
+    };
+
+    let Some(device_match) = self
+      .match_id(device_config, device.destination.clone(), None)
+      .timeout(timeout_from_chrono(config.modbus.discovery_timeout))
+      .await
+      .ok()
+      .flatten()
+    else {
+      tracing::warn!(
+        "Failed verifying static {} device id on {:?}",
+        device.kind,
+        device.destination
+      );
+      return None;
+    };
+
+    Some(self.identify(config, device_match).await)
+  }
+
   #[tracing::instrument(skip(self, config))]
   async fn match_modbus_device(
     &self,
@@ -104,19 +229,65 @@ impl Process {
       return vec![device_match];
     }
 
-    join_all(
+    futures::stream::iter(
       modbus::Destination::slaves_for(
         modbus_device,
         Some(config.modbus.max_slave),
       )
       .map(|destination| self.match_destination(config, destination)),
     )
+    .buffer_unordered(config.network.concurrency)
+    .collect::<Vec<_>>()
     .await
     .into_iter()
     .flatten()
     .collect::<Vec<_>>()
   }
 
+  #[tracing::instrument(skip(self, config))]
+  async fn match_serial_port(
+    &self,
+    config: &config::Values,
+    port: serial::SerialPort,
+  ) -> Vec<DeviceMatch> {
+    let detect = port.lines.len() > 1;
+    for line in port.lines {
+      let modbus_device = Device::Rtu {
+        path: port.path.clone(),
+        line,
+      };
+
+      // NOTE: keep the line of already bound devices so we don't
+      // disconnect them by trying other lines
+      if let Some(bound) =
+        self.services.modbus().bound_device(&modbus_device).await
+      {
+        return self.match_modbus_device(config, bound).await;
+      }
+
+      let device_matches = self
+        .match_modbus_device(config, modbus_device.clone())
+        .await;
+      if !device_matches.is_empty() {
+        if detect {
+          tracing::debug!("Detected line {:?} on {:?}", line, port.path);
+        }
+
+        return device_matches;
+      }
+
+      if detect {
+        self
+          .services
+          .modbus()
+          .stop_from_address(modbus_device)
+          .await;
+      }
+    }
+
+    Vec::new()
+  }
+
   #[tracing::instrument(skip(self, config))]
   async fn match_destination(
     &self,
@@ -124,25 +295,66 @@ impl Process {
     destination: modbus::Destination,
   ) -> Option<DeviceMatch> {
     let matching_destination = destination.clone();
-    let device = join_all(config.modbus.devices.values().map(move |device| {
-      let matching_destination = matching_destination.clone();
-      Box::pin(
-        self
-          .match_device(device.clone(), matching_destination)
-          .timeout(timeout_from_chrono(config.modbus.discovery_timeout)),
-      )
-    }))
-    .await
-    .into_iter()
-    .find(|device| device.as_ref().ok().is_some_and(|x| x.is_some()))?
-    .ok()??;
+    let mut candidates =
+      join_all(config.modbus.devices.values().map(move |device| {
+        let matching_destination = matching_destination.clone();
+        Box::pin(
+          self
+            .match_device(device.clone(), matching_destination)
+            .timeout(timeout_from_chrono(config.modbus.discovery_timeout)),
+        )
+      }))
+      .await
+      .into_iter()
+      .filter_map(|device| device.ok().flatten())
+      .collect::<Vec<_>>();
+    candidates.sort_by(|(device, specificity), (other, other_specificity)| {
+      other
+        .priority
+        .cmp(&device.priority)
+        .then(other_specificity.cmp(specificity))
+        .then(device.kind.cmp(&other.kind))
+    });
+    if let [(device, specificity), (other, other_specificity), ..] =
+      candidates.as_slice()
+    {
+      if device.priority == other.priority && specificity == other_specificity {
+        tracing::warn!(
+          "Ambiguous devices {:?} on {:?} so picked {}",
+          candidates
+            .iter()
+            .map(|(device, _)| device.kind.as_str())
+            .collect::<Vec<_>>(),
+          destination,
+          device.kind
+        );
+      }
+    }
+    let configured = candidates.into_iter().next().map(|(device, _)| device);
+
+    // NOTE: configured kinds go first so devices implementing sunspec
+    // can still be handled by hand written configs
+    let (device, sunspec) = match configured {
+      Some(device) => (device, None),
+      None if config.modbus.sunspec => {
+        let (device, chain) = self
+          .match_sunspec(destination.clone())
+          .timeout(timeout_from_chrono(config.modbus.discovery_timeout))
+          .await
+          .ok()
+          .flatten()?;
+        (device, Some(chain))
+      }
+      None => return None,
+    };
 
     let device_match = self
-      .match_id(device, destination)
+      .match_id(device, destination, sunspec)
       .timeout(timeout_from_chrono(config.modbus.discovery_timeout))
       .await
       .ok()
       .flatten()?;
+    let device_match = self.identify(config, device_match).await;
 
     tracing::debug!(
       "Matched {:?} devices on {:?}",
@@ -153,11 +365,29 @@ impl Process {
     Some(device_match)
   }
 
+  async fn identify(
+    &self,
+    config: &config::Values,
+    mut device_match: DeviceMatch,
+  ) -> DeviceMatch {
+    if config.modbus.identification {
+      device_match.identification = self
+        .read_identification(device_match.destination.clone())
+        .timeout(timeout_from_chrono(config.modbus.discovery_timeout))
+        .await
+        .ok()
+        .flatten();
+    }
+
+    device_match
+  }
+
   #[tracing::instrument(skip(self))]
   async fn consolidate(
     &self,
     device_match: DeviceMatch,
   ) -> Option<DeviceMatch> {
+    let db_device = DbDevice::from(&device_match.destination.device);
     match self
       .services
       .db()
@@ -176,19 +406,17 @@ impl Process {
           .db()
           .update_device_destination(
             &device_match.id,
-            match &device_match.destination.device {
-              Device::Tcp(address) => Some(db::to_db_address(address.ip())),
-              Device::Rtu { .. } => None,
-            },
-            match &device_match.destination.device {
-              Device::Tcp(_) => None,
-              Device::Rtu { path, .. } => Some(path.clone()),
-            },
-            match &device_match.destination.device {
-              Device::Tcp(_) => None,
-              Device::Rtu { baud_rate, .. } => Some(*baud_rate as i32),
-            },
+            db_device.address,
+            db_device.path,
+            db_device.baud_rate,
+            db_device.parity,
+            db_device.stop_bits,
+            db_device.data_bits,
             db::to_db_slave(device_match.destination.slave),
+            db_device.transport,
+            db_device.port,
+            device_match.sunspec.clone(),
+            device_match.identification.clone(),
             now,
             now,
           )
@@ -210,19 +438,17 @@ impl Process {
             status: db::DeviceStatus::Healthy,
             seen: now,
             pinged: now,
-            address: match &device_match.destination.device {
-              Device::Tcp(address) => Some(db::to_db_address(address.ip())),
-              Device::Rtu { .. } => None,
-            },
-            path: match &device_match.destination.device {
-              Device::Tcp(_) => None,
-              Device::Rtu { path, .. } => Some(path.clone()),
-            },
-            baud_rate: match &device_match.destination.device {
-              Device::Tcp(_) => None,
-              Device::Rtu { baud_rate, .. } => Some(*baud_rate as i32),
-            },
+            address: db_device.address,
+            path: db_device.path,
+            baud_rate: db_device.baud_rate,
+            parity: db_device.parity,
+            stop_bits: db_device.stop_bits,
+            data_bits: db_device.data_bits,
             slave: db::to_db_slave(device_match.destination.slave),
+            transport: db_device.transport,
+            port: db_device.port,
+            sunspec: device_match.sunspec.clone(),
+            identification: device_match.identification.clone(),
           })
           .await
         {
@@ -236,7 +462,12 @@ impl Process {
     self
       .services
       .modbus()
-      .bind(device_match.id.clone(), device_match.destination.clone())
+      .bind(
+        device_match.id.clone(),
+        device_match.destination.clone(),
+        device_match.max_batch_quantity,
+        device_match.turnaround_delay,
+      )
       .await;
 
     tracing::debug!("Matched device");
@@ -244,47 +475,277 @@ impl Process {
     Some(device_match)
   }
 
+  // NOTE: specificity is the number of detect registers that matched
+  // and empty detect groups never match so that kinds made only
+  // for extending don't match every device
   async fn match_device(
     &self,
     device: config::Device,
     destination: modbus::Destination,
-  ) -> Option<config::Device> {
-    let registers = self
-      .services
-      .modbus()
-      .read_from_destination(destination, device.detect.clone())
-      .await
-      .ok()?;
-
-    let matches = registers
-      .into_iter()
-      .map(|register| register.matches())
+  ) -> Option<(config::Device, usize)> {
+    let groups = std::iter::once(&device.detect)
+      .chain(device.alternatives.iter())
+      .filter(|group| !group.is_empty())
+      .filter(|group| {
+        let identifies = group.iter().any(|register| {
+          register.function == modbus::span::Function::DeviceIdentification
+        });
+        if identifies && !destination.device.supports_identification() {
+          self.report_unidentifiable(&device.kind);
+          return false;
+        }
+        true
+      })
+      .cloned()
       .collect::<Vec<_>>();
 
-    matches
-      .into_iter()
-      .all(std::convert::identity)
-      .then_some(device)
+    for group in groups {
+      let specificity = group.len();
+      let Ok(registers) = self
+        .services
+        .modbus()
+        .read_from_destination(
+          destination.clone(),
+          group,
+          device.max_batch_quantity,
+        )
+        .await
+      else {
+        continue;
+      };
+
+      if registers.len() == specificity
+        && registers.iter().all(|register| register.matches())
+      {
+        return Some((device, specificity));
+      }
+    }
+
+    None
+  }
+
+  fn report_unidentifiable(&self, kind: &str) {
+    let mut reported = match self.unidentifiable.lock() {
+      Ok(reported) => reported,
+      Err(poisoned) => poisoned.into_inner(),
+    };
+    if reported.insert(kind.to_owned()) {
+      tracing::warn!(
+        "Skipping identification detect of {} over rtu which can't frame it",
+        kind
+      );
+    }
+  }
+
+  pub(crate) async fn match_sunspec(
+    &self,
+    destination: modbus::Destination,
+  ) -> Option<(config::Device, serde_json::Value)> {
+    for base in modbus::sunspec::BASE_ADDRESSES {
+      let marker = self
+        .services
+        .modbus()
+        .read_from_destination(
+          destination.clone(),
+          vec![modbus::sunspec::marker_register(base)],
+          None,
+        )
+        .await;
+      if !marker.is_ok_and(|registers| {
+        !registers.is_empty()
+          && registers.iter().all(|register| register.matches())
+      }) {
+        continue;
+      }
+
+      let chain = self.walk_sunspec(destination.clone(), base).await?;
+      tracing::debug!("Found sunspec models {:?}", chain.models);
+      let device = modbus::sunspec::device(&chain)?;
+      let chain = match serde_json::to_value(&chain) {
+        Ok(chain) => chain,
+        Err(error) => {
+          tracing::warn!("Failed serializing sunspec models {}", error);
+          return None;
+        }
+      };
+
+      return Some((device, chain));
+    }
+
+    None
+  }
+
+  async fn walk_sunspec(
+    &self,
+    destination: modbus::Destination,
+    base: tokio_modbus::Address,
+  ) -> Option<modbus::sunspec::Chain> {
+    let mut models = Vec::new();
+    let mut address = base.checked_add(modbus::sunspec::HEADER_LENGTH)?;
+    while models.len() < modbus::sunspec::MAX_MODELS {
+      let header = self
+        .services
+        .modbus()
+        .read_from_destination(
+          destination.clone(),
+          vec![modbus::sunspec::header_register(address)],
+          None,
+        )
+        .await
+        .ok()?;
+      let (id, length) = match &header.first()?.storage {
+        modbus::RegisterValueStorage::Raw(storage) => (
+          storage.value.first().copied()?,
+          storage.value.get(1).copied()?,
+        ),
+        _ => return None,
+      };
+      if id == modbus::sunspec::END {
+        return Some(modbus::sunspec::Chain { base, models });
+      }
+
+      let body = address.checked_add(modbus::sunspec::HEADER_LENGTH)?;
+      models.push(modbus::sunspec::Model {
+        id,
+        address: body,
+        length,
+      });
+      address = body.checked_add(length)?;
+    }
+
+    tracing::warn!(
+      "Stopped walking sunspec models after {:?} models",
+      models.len()
+    );
+
+    Some(modbus::sunspec::Chain { base, models })
   }
 
   async fn match_id(
     &self,
     device: config::Device,
     destination: modbus::Destination,
+    sunspec: Option<serde_json::Value>,
   ) -> Option<DeviceMatch> {
     let matching_destination = destination.clone();
+    let id_len = device.id.len();
     let registers = self
       .services
       .modbus()
-      .read_from_destination(matching_destination, device.id)
+      .read_from_destination(
+        matching_destination,
+        device.id,
+        device.max_batch_quantity,
+      )
       .await;
 
-    registers.ok().map(|id_registers| DeviceMatch {
+    // NOTE: ids with illegal registers would never match again
+    let registers = registers
+      .ok()
+      .filter(|id_registers| id_registers.len() == id_len);
+
+    registers.map(|id_registers| DeviceMatch {
       kind: device.kind.clone(),
       destination,
+      max_batch_quantity: device.max_batch_quantity,
+      turnaround_delay: device.turnaround_delay,
+      sunspec,
+      identification: None,
       id: modbus::make_id(device.kind, id_registers),
     })
   }
+
+  // NOTE: devices without the vendor name object don't support
+  // identification so the rest of the objects are not even tried
+  async fn read_identification(
+    &self,
+    destination: modbus::Destination,
+  ) -> Option<serde_json::Value> {
+    let mut objects = serde_json::Map::new();
+    for (object, name) in modbus::identification::OBJECTS {
+      let registers = self
+        .services
+        .modbus()
+        .read_from_destination(
+          destination.clone(),
+          vec![modbus::identification::object_register(object)],
+          None,
+        )
+        .await;
+      let value = registers.ok().and_then(|registers| {
+        registers
+          .into_iter()
+          .find_map(|register| match register.storage {
+            modbus::RegisterValueStorage::String(value) => {
+              Some(value.value.trim_end_matches('\0').to_string())
+            }
+            _ => None,
+          })
+      });
+
+      match value {
+        Some(value) => {
+          objects.insert(name.to_string(), serde_json::Value::String(value));
+        }
+        None if object == modbus::identification::VENDOR_NAME => return None,
+        None => {}
+      }
+    }
+    tracing::debug!("Read identification {:?}", objects);
+
+    Some(serde_json::Value::Object(objects))
+  }
+}
+
+struct DbDevice {
+  address: Option<sqlx::types::ipnetwork::IpNetwork>,
+  path: Option<String>,
+  baud_rate: Option<i32>,
+  parity: Option<db::DeviceParity>,
+  stop_bits: Option<i32>,
+  data_bits: Option<i32>,
+  transport: Option<db::DeviceTransport>,
+  port: Option<i32>,
+}
+
+impl From<&Device> for DbDevice {
+  fn from(device: &Device) -> Self {
+    match device {
+      Device::Tcp(address) => Self::network(db::DeviceTransport::Tcp, address),
+      Device::RtuOverTcp(address) => {
+        Self::network(db::DeviceTransport::RtuOverTcp, address)
+      }
+      Device::Udp(address) => Self::network(db::DeviceTransport::Udp, address),
+      Device::Rtu { path, line } => Self {
+        address: None,
+        path: Some(path.clone()),
+        baud_rate: Some(line.baud_rate as i32),
+        parity: Some(db::to_db_parity(line.parity)),
+        stop_bits: Some(db::to_db_stop_bits(line.stop_bits)),
+        data_bits: Some(db::to_db_data_bits(line.data_bits)),
+        transport: Some(db::DeviceTransport::Rtu),
+        port: None,
+      },
+    }
+  }
+}
+
+impl DbDevice {
+  fn network(
+    transport: db::DeviceTransport,
+    address: &std::net::SocketAddr,
+  ) -> Self {
+    Self {
+      address: Some(db::to_db_address(address.ip())),
+      path: None,
+      baud_rate: None,
+      parity: None,
+      stop_bits: None,
+      data_bits: None,
+      transport: Some(transport),
+      port: Some(i32::from(address.port())),
+    }
+  }
 }
 
 fn timeout_from_chrono(
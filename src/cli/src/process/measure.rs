@@ -69,13 +69,25 @@ type BoxedMeasurementStream = Pin<
   >,
 >;
 
+// NOTE: one per measurement group of a device where ungrouped
+// measurements have no group and no interval
 #[derive(Clone, Debug)]
 struct Device {
   id: String,
   kind: String,
+  group: Option<String>,
+  interval: Option<chrono::Duration>,
   id_registers: Vec<modbus::IdRegister<modbus::RegisterKindStorage>>,
   measurement_registers:
     Vec<modbus::MeasurementRegister<modbus::RegisterKindStorage>>,
+  scale_registers:
+    Vec<modbus::MeasurementRegister<modbus::RegisterKindStorage>>,
+}
+
+impl Device {
+  fn key(&self) -> (&str, Option<&str>) {
+    (self.id.as_str(), self.group.as_deref())
+  }
 }
 
 struct DeviceStream {
@@ -120,6 +132,9 @@ impl Process {
         Some(Some(Err(modbus::ServerReadError::ParsingFailed(error)))) => {
           tracing::warn!("Parsing failed {:?} {}", device.id, error);
         }
+        Some(Some(Err(modbus::ServerReadError::Exception(error)))) => {
+          tracing::warn!("Device exception {:?} {}", device.id, error);
+        }
         Some(Some(Ok(registers))) => {
           measurements.push(DeviceRegisters {
             device: device.clone(),
@@ -159,18 +174,11 @@ impl Process {
       .into_iter()
       .filter(|device| device.status == db::DeviceStatus::Healthy)
       .filter_map(|device| {
-        config
-          .modbus
-          .devices
-          .values()
-          .find(|device_config| device_config.kind == device.kind)
-          .map(|config| Device {
-            id: device.id,
-            kind: device.kind,
-            id_registers: config.id.clone(),
-            measurement_registers: config.measurement.clone(),
-          })
+        super::device_config(&config, &device)
+          .map(|config| Self::group_devices(device, &config))
       })
+      .flatten()
+      .sorted_by(|x, y| Ord::cmp(&x.key(), &y.key()))
       .collect::<Vec<_>>();
     let merged_devices_len = merged_devices.len();
 
@@ -183,6 +191,50 @@ impl Process {
     Ok(merged_devices)
   }
 
+  fn group_devices(device: db::Device, config: &config::Device) -> Vec<Device> {
+    let all_registers = config
+      .measurement
+      .iter()
+      .chain(
+        config
+          .groups
+          .iter()
+          .flat_map(|group| group.measurement.iter()),
+      )
+      .collect::<Vec<_>>();
+    let scale_registers = |registers| {
+      modbus::referenced_scale_registers(
+        registers,
+        all_registers.iter().copied(),
+      )
+    };
+
+    let ungrouped = (!config.measurement.is_empty()
+      || config.groups.is_empty())
+    .then(|| Device {
+      id: device.id.clone(),
+      kind: device.kind.clone(),
+      group: None,
+      interval: None,
+      id_registers: config.id.clone(),
+      measurement_registers: config.measurement.clone(),
+      scale_registers: scale_registers(&config.measurement),
+    });
+
+    ungrouped
+      .into_iter()
+      .chain(config.groups.iter().map(|group| Device {
+        id: device.id.clone(),
+        kind: device.kind.clone(),
+        group: Some(group.name.clone()),
+        interval: Some(group.interval),
+        id_registers: config.id.clone(),
+        measurement_registers: group.measurement.clone(),
+        scale_registers: scale_registers(&group.measurement),
+      }))
+      .collect()
+  }
+
   #[tracing::instrument(skip_all)]
   async fn merge_devices(
     &self,
@@ -195,7 +247,7 @@ impl Process {
     let merged_devices = devices
       .drain(0..)
       .merge_join_by(new_devices.into_iter(), |x, y| {
-        Ord::cmp(x.device.id.as_str(), y.id.as_str())
+        Ord::cmp(&x.device.key(), &y.key())
       })
       .filter_map(|x| match x {
         itertools::EitherOrBoth::Both(old_device, new_device) => {
@@ -271,7 +323,7 @@ impl Process {
           return None;
         }
 
-        let timestamp =
+        let id_timestamp =
           match measurement.registers.iter().cloned().find_map(Either::left) {
             None => {
               tracing::warn! {
@@ -285,9 +337,45 @@ impl Process {
           .storage
           .timestamp();
 
-        let data = modbus::serialize_registers(
-          measurement.registers.into_iter().filter_map(Either::right),
+        // NOTE: groups are read separately so each sample gets the
+        // timestamp of its own measurement registers
+        let timestamp = measurement
+          .registers
+          .iter()
+          .find_map(|register| register.as_ref().right())
+          .map_or(id_timestamp, |register| register.storage.timestamp());
+
+        self.services.gateway().update(
+          &source,
+          timestamp,
+          &measurement.device.measurement_registers,
+          measurement
+            .registers
+            .iter()
+            .filter_map(|register| register.as_ref().right()),
+        );
+
+        // NOTE: the gateway gets unscaled values so it can serve
+        // scale registers alongside the registers they scale
+        let mut registers = measurement
+          .registers
+          .into_iter()
+          .filter_map(Either::right)
+          .collect::<Vec<_>>();
+        modbus::apply_scale_registers(
+          &measurement.device.measurement_registers,
+          &mut registers,
         );
+        // NOTE: scale registers of other groups are stored with their own
+        registers.retain(|register| {
+          !measurement
+            .device
+            .scale_registers
+            .iter()
+            .any(|kind| kind.name == register.name)
+        });
+
+        let data = modbus::serialize_registers(registers);
 
         Some(db::Measurement {
           id: 0,
@@ -332,9 +420,11 @@ impl Process {
           device
             .measurement_registers
             .into_iter()
+            .chain(device.scale_registers)
             .map(Either::Right)
             .chain(device.id_registers.into_iter().map(Either::Left))
             .collect::<Vec<_>>(),
+          device.interval,
         )
         .await?,
     ))
@@ -1,5 +1,5 @@
 mod daily;
-mod discover;
+pub(crate) mod discover;
 mod health;
 mod measure;
 mod nightly;
@@ -9,13 +9,17 @@ mod push;
 mod time;
 mod update;
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
 use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
 
-use crate::{config, service};
+use crate::{
+  config,
+  service::{self, db, modbus},
+};
 
 // FIXME: remove warnings about dead code for unused processes
 
@@ -30,10 +34,50 @@ pub(crate) trait Recurring: Process {
   async fn execute(&self) -> anyhow::Result<()>;
 }
 
+// NOTE: sunspec devices carry their model chain in the db
+// instead of having a configured device kind
+fn device_config(
+  config: &config::Values,
+  device: &db::Device,
+) -> Option<config::Device> {
+  match &device.sunspec {
+    Some(chain) if device.kind == modbus::sunspec::KIND => {
+      match serde_json::from_value(chain.clone()) {
+        Ok(chain) => modbus::sunspec::device(&chain),
+        Err(error) => {
+          tracing::warn!("Failed parsing sunspec models {}", error);
+          None
+        }
+      }
+    }
+    _ => config
+      .modbus
+      .devices
+      .values()
+      .find(|device_config| device_config.kind == device.kind)
+      .cloned(),
+  }
+}
+
+type Shared = Arc<Mutex<dyn Recurring + Send + Sync>>;
+
+type Cron = fn(&config::Schedule) -> cron::Schedule;
+
+// NOTE: processes outlive their jobs so that rescheduling keeps whatever
+// state they built up like measurement streams
+struct Scheduled {
+  uuid: uuid::Uuid,
+  process: Shared,
+  cron: Cron,
+}
+
+#[derive(Clone)]
 pub(crate) struct Container {
   config: config::Manager,
   services: service::Container,
   scheduler: Arc<Mutex<Option<JobScheduler>>>,
+  jobs: Arc<Mutex<HashMap<&'static str, Scheduled>>>,
+  listener: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 #[derive(Debug, Error)]
@@ -47,6 +91,9 @@ pub(crate) enum ContainerError {
   #[error("Job addition startup failed")]
   JobAddition(JobSchedulerError),
 
+  #[error("Job removal failed")]
+  JobRemoval(JobSchedulerError),
+
   #[error("Job scheduler startup failed")]
   StartupFailed(JobSchedulerError),
 
@@ -63,38 +110,10 @@ macro_rules! add_job_impl {
     {
       $startup(process.clone()).await;
     }
-    match Job::new_async_tz(
-      $config.schedule.$name,
-      $config.schedule.timezone,
-      move |uuid, mut lock| {
-        let process = process.clone();
-        Box::pin(async move {
-          let process = process.clone().lock_owned().await;
-          tracing::debug!("Starting execution of {}", process.process_name());
-          match lock.next_tick_for_job(uuid).await {
-            Ok(Some(_)) => {
-              if let Err(error) = process.execute().await {
-                tracing::error!(
-                  "Process execution failed {} for {}",
-                  error,
-                  process.process_name()
-                );
-              }
-            }
-            _ => println!("Could not get next tick for 7s job"),
-          }
-        })
-      },
-    ) {
-      Ok(job) => {
-        if let Err(error) = $scheduler.add(job).await {
-          return Err(ContainerError::JobAddition(error));
-        }
-      }
-      Err(error) => {
-        return Err(ContainerError::JobCreation(error));
-      }
-    };
+    let cron: Cron = |schedule| schedule.$name.clone();
+    $self
+      .add_job(&$scheduler, stringify!($name), process, cron, &$config)
+      .await?;
   }};
 }
 
@@ -131,6 +150,39 @@ macro_rules! run_add_job {
   };
 }
 
+fn make_job(
+  process: Shared,
+  config: &config::Values,
+  cron: Cron,
+) -> Result<Job, JobSchedulerError> {
+  Job::new_async_tz(
+    cron(&config.schedule),
+    config.schedule.timezone,
+    move |uuid, mut lock| {
+      let process = process.clone();
+      Box::pin(async move {
+        let process = process.clone().lock_owned().await;
+        tracing::debug!("Starting execution of {}", process.process_name());
+        match lock.next_tick_for_job(uuid).await {
+          Ok(Some(_)) => {
+            if let Err(error) = process.execute().await {
+              tracing::error!(
+                "Process execution failed {} for {}",
+                error,
+                process.process_name()
+              );
+            }
+          }
+          _ => tracing::warn!(
+            "Could not get next tick for {}",
+            process.process_name()
+          ),
+        }
+      })
+    },
+  )
+}
+
 impl Container {
   pub(crate) fn new(
     config: config::Manager,
@@ -140,10 +192,15 @@ impl Container {
       config,
       services,
       scheduler: Arc::new(Mutex::new(None)),
+      jobs: Arc::new(Mutex::new(HashMap::new())),
+      listener: Arc::new(Mutex::new(None)),
     }
   }
 
   pub(crate) async fn startup(&self) -> Result<(), ContainerError> {
+    // NOTE: subscribe before the first poll so its remote config
+    // gets applied too
+    let changes = self.config.subscribe();
     let config = self.config.values().await;
     let scheduler = match JobScheduler::new().await {
       Ok(scheduler) => scheduler,
@@ -176,10 +233,117 @@ impl Container {
       *scheduler_mutex = Some(scheduler);
     }
 
+    {
+      let mut listener = self.listener.clone().lock_owned().await;
+      *listener = Some(tokio::spawn(self.clone().listen(changes)));
+    }
+
+    Ok(())
+  }
+
+  async fn add_job(
+    &self,
+    scheduler: &JobScheduler,
+    name: &'static str,
+    process: Shared,
+    cron: Cron,
+    config: &config::Values,
+  ) -> Result<(), ContainerError> {
+    let job = match make_job(process.clone(), config, cron) {
+      Ok(job) => job,
+      Err(error) => return Err(ContainerError::JobCreation(error)),
+    };
+    let uuid = match scheduler.add(job).await {
+      Ok(uuid) => uuid,
+      Err(error) => return Err(ContainerError::JobAddition(error)),
+    };
+
+    let mut jobs = self.jobs.clone().lock_owned().await;
+    jobs.insert(
+      name,
+      Scheduled {
+        uuid,
+        process,
+        cron,
+      },
+    );
+
+    Ok(())
+  }
+
+  async fn listen(self, mut changes: broadcast::Receiver<config::Changes>) {
+    loop {
+      let changes = match changes.recv().await {
+        Ok(changes) => changes,
+        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+          tracing::warn!("Missed {} config changes", skipped);
+          Arc::new(vec![String::new()])
+        }
+        Err(broadcast::error::RecvError::Closed) => break,
+      };
+
+      let config = self.config.values().await;
+      self.services.reconfigure(config.clone(), &changes).await;
+      if let Err(error) = self.reschedule(&config, &changes).await {
+        tracing::error!("Failed rescheduling jobs {}", error);
+      }
+
+      // NOTE: static devices are bound here so that file reloads and
+      // remote configs bind them the same way and only when they change
+      if config::changed(&changes, "modbus") {
+        let bound =
+          discover::Process::new(self.config.clone(), self.services.clone())
+            .bind_static(&config)
+            .await;
+        tracing::debug!("Bound {} static devices", bound);
+      }
+    }
+  }
+
+  #[tracing::instrument(skip(self, config))]
+  async fn reschedule(
+    &self,
+    config: &config::Values,
+    changes: &[String],
+  ) -> Result<(), ContainerError> {
+    let scheduler = self.scheduler.clone().lock_owned().await;
+    let Some(scheduler) = &*scheduler else {
+      return Ok(());
+    };
+
+    let timezone_changed = config::changed(changes, "schedule.timezone");
+    let mut jobs = self.jobs.clone().lock_owned().await;
+    for (name, scheduled) in jobs.iter_mut() {
+      if !timezone_changed
+        && !config::changed(changes, &format!("schedule.{name}"))
+      {
+        continue;
+      }
+
+      if let Err(error) = scheduler.remove(&scheduled.uuid).await {
+        return Err(ContainerError::JobRemoval(error));
+      }
+      let job =
+        match make_job(scheduled.process.clone(), config, scheduled.cron) {
+          Ok(job) => job,
+          Err(error) => return Err(ContainerError::JobCreation(error)),
+        };
+      scheduled.uuid = match scheduler.add(job).await {
+        Ok(uuid) => uuid,
+        Err(error) => return Err(ContainerError::JobAddition(error)),
+      };
+
+      tracing::info!("Rescheduled {} job", name);
+    }
+
     Ok(())
   }
 
   pub(crate) async fn shutdown(&self) -> Result<(), ContainerError> {
+    if let Some(listener) = self.listener.clone().lock_owned().await.take() {
+      listener.abort();
+    }
+
     let mut scheduler = self.scheduler.clone().lock_owned().await;
     if let Some(scheduler) = &mut *scheduler {
       if let Err(error) = scheduler.shutdown().await {
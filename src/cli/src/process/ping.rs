@@ -102,12 +102,7 @@ impl Process {
     config: &config::Values,
     device: db::Device,
   ) -> bool {
-    match config
-      .modbus
-      .devices
-      .values()
-      .find(|device_config| device_config.kind == device.kind)
-    {
+    match super::device_config(config, &device) {
       Some(device_config) => {
         match self
           .services
@@ -125,7 +120,9 @@ impl Process {
             return false;
           }
           Ok(Ok(id_registers)) => {
-            if modbus::make_id(device.kind, id_registers) == device.id {
+            if id_registers.len() == device_config.id.len()
+              && modbus::make_id(device.kind, id_registers) == device.id
+            {
               tracing::debug!("Id match");
             } else {
               tracing::debug!("Id mismatch");
@@ -162,8 +159,11 @@ impl Process {
     };
     let seen = if pinged { now } else { device.seen };
     let update = device.status != status;
+    // NOTE: static devices stay bound so they are picked up as soon as
+    // they are reachable again
     let remove = (status == db::DeviceStatus::Inactive)
-      && (device.status != db::DeviceStatus::Inactive);
+      && (device.status != db::DeviceStatus::Inactive)
+      && !self.is_static(config, &device);
 
     if let Err(error) = self
       .services
@@ -178,37 +178,33 @@ impl Process {
     if remove {
       self.services.modbus().stop_from_id(&device.id).await;
     } else {
+      let device_config = super::device_config(config, &device);
       self
         .services
         .modbus()
         .bind(
           device.id.clone(),
           modbus::Destination {
-            device: match &device.address {
-              Some(address) => modbus::connection::Device::Tcp(
-                self.services.net().to_socket(db::to_address(*address)),
-              ),
-              None => match (&device.path, &device.baud_rate) {
-                (Some(path), Some(baud_rate)) => {
-                  modbus::connection::Device::Rtu {
-                    path: path.clone(),
-                    baud_rate: (*baud_rate as u32),
-                  }
-                }
-                _ => {
-                  return Err(anyhow::anyhow!(format!(
-                    "Device {device:?} missing appropriate server details"
-                  )))
-                }
-              },
-            },
+            device: self.to_modbus_device(&device)?,
             slave: db::to_slave(device.slave),
           },
+          device_config
+            .as_ref()
+            .and_then(|device_config| device_config.max_batch_quantity),
+          device_config
+            .as_ref()
+            .and_then(|device_config| device_config.turnaround_delay),
         )
         .await;
     }
 
     if update {
+      let mut data = serde_json::Map::new();
+      if let Some(desyncs) =
+        self.services.modbus().desyncs_from_id(&device.id).await
+      {
+        data.insert("desyncs".to_string(), serde_json::json!(desyncs));
+      }
       if let Err(error) = self
         .services
         .db()
@@ -217,7 +213,7 @@ impl Process {
           source: device.id.clone(),
           timestamp: seen,
           status,
-          data: serde_json::Value::Object(serde_json::Map::new()),
+          data: serde_json::Value::Object(data),
         })
         .await
       {
@@ -231,6 +227,88 @@ impl Process {
   }
 }
 
+impl Process {
+  fn is_static(&self, config: &config::Values, device: &db::Device) -> bool {
+    let Ok(modbus_device) = self.to_modbus_device(device) else {
+      return false;
+    };
+    let destination = modbus::Destination {
+      device: modbus_device,
+      slave: db::to_slave(device.slave),
+    };
+
+    config
+      .modbus
+      .r#static
+      .iter()
+      .any(|static_device| static_device.destination == destination)
+  }
+
+  fn to_modbus_device(
+    &self,
+    device: &db::Device,
+  ) -> anyhow::Result<modbus::connection::Device> {
+    let socket = |port: Option<i32>| {
+      device.address.map(|address| {
+        let address = db::to_address(address);
+        match port.and_then(|port| u16::try_from(port).ok()) {
+          Some(port) => std::net::SocketAddr::new(address, port),
+          None => self.services.net().to_socket(address),
+        }
+      })
+    };
+
+    // NOTE: devices without a transport were stored before udp and rtu over
+    // tcp so they are either tcp or rtu depending on what is set
+    let transport = match device.transport {
+      Some(transport) => transport,
+      None if device.address.is_some() => db::DeviceTransport::Tcp,
+      None => db::DeviceTransport::Rtu,
+    };
+
+    let modbus_device = match transport {
+      db::DeviceTransport::Tcp => {
+        socket(device.port).map(modbus::connection::Device::Tcp)
+      }
+      db::DeviceTransport::RtuOverTcp => device
+        .port
+        .and(socket(device.port))
+        .map(modbus::connection::Device::RtuOverTcp),
+      db::DeviceTransport::Udp => device
+        .port
+        .and(socket(device.port))
+        .map(modbus::connection::Device::Udp),
+      db::DeviceTransport::Rtu => match (&device.path, &device.baud_rate) {
+        (Some(path), Some(baud_rate)) => {
+          let default = modbus::connection::Line::default();
+          Some(modbus::connection::Device::Rtu {
+            path: path.clone(),
+            line: modbus::connection::Line {
+              baud_rate: (*baud_rate as u32),
+              parity: device.parity.map_or(default.parity, db::to_parity),
+              stop_bits: device
+                .stop_bits
+                .and_then(db::to_stop_bits)
+                .unwrap_or(default.stop_bits),
+              data_bits: device
+                .data_bits
+                .and_then(db::to_data_bits)
+                .unwrap_or(default.data_bits),
+            },
+          })
+        }
+        _ => None,
+      },
+    };
+
+    modbus_device.ok_or_else(|| {
+      anyhow::anyhow!(format!(
+        "Device {device:?} missing appropriate server details"
+      ))
+    })
+  }
+}
+
 fn timeout_from_chrono(
   timeout: chrono::Duration,
 ) -> futures_time::time::Duration {
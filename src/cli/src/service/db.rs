@@ -24,6 +24,23 @@ pub(crate) enum DeviceStatus {
   Inactive,
 }
 
+#[derive(Debug, Copy, Clone, Type, Eq, PartialEq)]
+#[sqlx(type_name = "device_transport", rename_all = "snake_case")]
+pub(crate) enum DeviceTransport {
+  Tcp,
+  Rtu,
+  RtuOverTcp,
+  Udp,
+}
+
+#[derive(Debug, Copy, Clone, Type, Eq, PartialEq)]
+#[sqlx(type_name = "device_parity", rename_all = "lowercase")]
+pub(crate) enum DeviceParity {
+  None,
+  Odd,
+  Even,
+}
+
 #[derive(Debug, Clone, FromRow)]
 pub(crate) struct Device {
   pub(crate) id: String,
@@ -34,7 +51,17 @@ pub(crate) struct Device {
   pub(crate) address: Option<IpNetwork>,
   pub(crate) path: Option<String>,
   pub(crate) baud_rate: Option<i32>,
+  pub(crate) parity: Option<DeviceParity>,
+  pub(crate) stop_bits: Option<i32>,
+  pub(crate) data_bits: Option<i32>,
   pub(crate) slave: Option<i32>,
+  // NOTE: null for devices stored before transports other than tcp and rtu
+  pub(crate) transport: Option<DeviceTransport>,
+  pub(crate) port: Option<i32>,
+  // NOTE: the model chain of devices discovered through sunspec
+  pub(crate) sunspec: Option<serde_json::Value>,
+  // NOTE: read device identification objects of devices supporting it
+  pub(crate) identification: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -158,7 +185,7 @@ impl Service {
     let devices = sqlx::query_as!(
       Device,
       r#"
-        select id, kind, status as "status: DeviceStatus", seen, pinged, address, path, baud_rate, slave
+        select id, kind, status as "status: DeviceStatus", seen, pinged, address, path, baud_rate, parity as "parity: DeviceParity", stop_bits, data_bits, slave, transport as "transport: DeviceTransport", port, sunspec, identification
         from devices
       "#,
     )
@@ -179,7 +206,7 @@ impl Service {
     let device = sqlx::query_as!(
       Device,
       r#"
-        select id, kind, status as "status: DeviceStatus", seen, pinged, address, path, baud_rate, slave
+        select id, kind, status as "status: DeviceStatus", seen, pinged, address, path, baud_rate, parity as "parity: DeviceParity", stop_bits, data_bits, slave, transport as "transport: DeviceTransport", port, sunspec, identification
         from devices
         where id = $1
       "#,
@@ -201,8 +228,8 @@ impl Service {
     #[allow(clippy::panic, reason = "sqlx thing")]
     sqlx::query!(
       r#"
-        insert into devices (id, kind, status, seen, pinged, address, path, baud_rate, slave)
-        values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        insert into devices (id, kind, status, seen, pinged, address, path, baud_rate, parity, stop_bits, data_bits, slave, transport, port, sunspec, identification)
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
       "#,
       device.id,
       device.kind,
@@ -212,7 +239,14 @@ impl Service {
       device.address,
       device.path,
       device.baud_rate,
-      device.slave
+      device.parity as Option<DeviceParity>,
+      device.stop_bits,
+      device.data_bits,
+      device.slave,
+      device.transport as Option<DeviceTransport>,
+      device.port,
+      device.sunspec,
+      device.identification
     )
     .execute(&self.pool)
     .await?;
@@ -279,7 +313,14 @@ impl Service {
     address: Option<IpNetwork>,
     path: Option<String>,
     baud_rate: Option<i32>,
+    parity: Option<DeviceParity>,
+    stop_bits: Option<i32>,
+    data_bits: Option<i32>,
     slave: Option<i32>,
+    transport: Option<DeviceTransport>,
+    port: Option<i32>,
+    sunspec: Option<serde_json::Value>,
+    identification: Option<serde_json::Value>,
     seen: DateTime<Utc>,
     pinged: DateTime<Utc>,
   ) -> Result<(), Error> {
@@ -287,14 +328,21 @@ impl Service {
     sqlx::query!(
       r#"
         update devices
-        set address = $2, path = $3, baud_rate = $4, slave = $5, seen = $6, pinged = $7
+        set address = $2, path = $3, baud_rate = $4, parity = $5, stop_bits = $6, data_bits = $7, slave = $8, transport = $9, port = $10, sunspec = $11, identification = $12, seen = $13, pinged = $14
         where id = $1
       "#,
       id,
       address,
       path,
       baud_rate,
+      parity as Option<DeviceParity>,
+      stop_bits,
+      data_bits,
       slave,
+      transport as Option<DeviceTransport>,
+      port,
+      sunspec,
+      identification,
       seen,
       pinged
     )
@@ -550,4 +598,58 @@ pub(crate) fn to_slave(db_slave: Option<i32>) -> Option<u8> {
   db_slave.map(|slave| slave as u8)
 }
 
+pub(crate) fn to_db_parity(parity: tokio_serial::Parity) -> DeviceParity {
+  match parity {
+    tokio_serial::Parity::None => DeviceParity::None,
+    tokio_serial::Parity::Odd => DeviceParity::Odd,
+    tokio_serial::Parity::Even => DeviceParity::Even,
+  }
+}
+
+pub(crate) fn to_db_stop_bits(stop_bits: tokio_serial::StopBits) -> i32 {
+  match stop_bits {
+    tokio_serial::StopBits::One => 1,
+    tokio_serial::StopBits::Two => 2,
+  }
+}
+
+pub(crate) fn to_db_data_bits(data_bits: tokio_serial::DataBits) -> i32 {
+  match data_bits {
+    tokio_serial::DataBits::Five => 5,
+    tokio_serial::DataBits::Six => 6,
+    tokio_serial::DataBits::Seven => 7,
+    tokio_serial::DataBits::Eight => 8,
+  }
+}
+
+pub(crate) fn to_parity(db_parity: DeviceParity) -> tokio_serial::Parity {
+  match db_parity {
+    DeviceParity::None => tokio_serial::Parity::None,
+    DeviceParity::Odd => tokio_serial::Parity::Odd,
+    DeviceParity::Even => tokio_serial::Parity::Even,
+  }
+}
+
+pub(crate) fn to_stop_bits(
+  db_stop_bits: i32,
+) -> Option<tokio_serial::StopBits> {
+  match db_stop_bits {
+    1 => Some(tokio_serial::StopBits::One),
+    2 => Some(tokio_serial::StopBits::Two),
+    _ => None,
+  }
+}
+
+pub(crate) fn to_data_bits(
+  db_data_bits: i32,
+) -> Option<tokio_serial::DataBits> {
+  match db_data_bits {
+    5 => Some(tokio_serial::DataBits::Five),
+    6 => Some(tokio_serial::DataBits::Six),
+    7 => Some(tokio_serial::DataBits::Seven),
+    8 => Some(tokio_serial::DataBits::Eight),
+    _ => None,
+  }
+}
+
 static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
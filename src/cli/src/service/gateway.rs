@@ -0,0 +1,288 @@
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+
+use tokio_modbus::prelude::*;
+use tokio_modbus::{Address, Quantity};
+
+use crate::*;
+
+use super::modbus::{
+  self,
+  batch::max_quantity_for,
+  connection::ExceptionCode,
+  server::{exception, function_code, words_to_bits},
+  span::Function,
+};
+
+// NOTE: reads are only ever served from the cache of the latest measurements
+// so a scada polling the gateway never causes any additional bus traffic
+
+#[derive(Debug, Clone)]
+pub(crate) struct Service {
+  enabled: bool,
+  address: SocketAddr,
+  cache: Arc<RwLock<Cache>>,
+  listener: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+}
+
+#[derive(Debug)]
+struct Cache {
+  stale_timeout: chrono::Duration,
+  ids: HashMap<String, u8>,
+  unmapped: HashSet<String>,
+  units: HashMap<u8, Unit>,
+}
+
+#[derive(Debug)]
+struct Unit {
+  id: String,
+  timestamp: chrono::DateTime<chrono::Utc>,
+  registers: HashMap<(Function, Address), u16>,
+}
+
+#[derive(Debug, Clone)]
+struct Server {
+  cache: Arc<RwLock<Cache>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum StartError {
+  #[error("Failed binding gateway listener")]
+  Bind(#[from] std::io::Error),
+}
+
+impl service::Service for Service {
+  fn new(config: config::Values) -> Self {
+    Self {
+      enabled: config.gateway.enabled,
+      address: config.gateway.address,
+      cache: Arc::new(RwLock::new(Cache {
+        stale_timeout: config.gateway.stale_timeout,
+        ids: config.gateway.units,
+        unmapped: HashSet::new(),
+        units: HashMap::new(),
+      })),
+      listener: Arc::new(Mutex::new(None)),
+    }
+  }
+}
+
+impl Service {
+  // NOTE: the cache is shared with the listener so the new unit mapping
+  // and stale timeout apply right away and cached units get dropped until
+  // the next measurement while the listener only restarts when it moves
+  #[tracing::instrument(skip(self, config))]
+  pub(crate) async fn reconfigure(&self, config: config::Values) -> Self {
+    let service = Self {
+      enabled: config.gateway.enabled,
+      address: config.gateway.address,
+      cache: self.cache.clone(),
+      listener: self.listener.clone(),
+    };
+
+    if let Ok(mut cache) = self.cache.write() {
+      cache.stale_timeout = config.gateway.stale_timeout;
+      cache.ids = config.gateway.units;
+      cache.unmapped.clear();
+      cache.units.clear();
+    }
+
+    if service.enabled != self.enabled || service.address != self.address {
+      self.stop();
+      if let Err(error) = service.start().await {
+        tracing::error!("Failed restarting gateway {}", error);
+      }
+    }
+
+    service
+  }
+
+  #[tracing::instrument(skip(self))]
+  pub(crate) async fn start(&self) -> Result<(), StartError> {
+    if !self.enabled {
+      return Ok(());
+    }
+
+    let listener = tokio::net::TcpListener::bind(self.address).await?;
+    tracing::info!("Serving gateway on {}", self.address);
+
+    let server = Server {
+      cache: self.cache.clone(),
+    };
+    let handle = tokio::spawn(async move {
+      let on_connected = |stream, address| {
+        let server = server.clone();
+        async move {
+          tokio_modbus::server::tcp::accept_tcp_connection(
+            stream,
+            address,
+            |_| Ok(Some(server.clone())),
+          )
+        }
+      };
+      let on_process_error = |error| {
+        tracing::warn!("Failed processing gateway connection {}", error);
+      };
+      if let Err(error) = tokio_modbus::server::tcp::Server::new(listener)
+        .serve(&on_connected, on_process_error)
+        .await
+      {
+        tracing::error!("Gateway stopped {}", error);
+      }
+    });
+
+    let mut listener = match self.listener.lock() {
+      Ok(listener) => listener,
+      Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(previous) = listener.replace(handle) {
+      previous.abort();
+    }
+
+    Ok(())
+  }
+
+  fn stop(&self) {
+    let mut listener = match self.listener.lock() {
+      Ok(listener) => listener,
+      Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(listener) = listener.take() {
+      tracing::info!("Stopping gateway on {}", self.address);
+      listener.abort();
+    }
+  }
+
+  #[tracing::instrument(skip(self, kinds, values))]
+  pub(crate) fn update<'a>(
+    &self,
+    id: &str,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    kinds: &[modbus::MeasurementRegister<modbus::RegisterKindStorage>],
+    values: impl IntoIterator<
+      Item = &'a modbus::MeasurementRegister<modbus::RegisterValueStorage>,
+    >,
+  ) {
+    if !self.enabled {
+      return;
+    }
+
+    let Ok(mut cache) = self.cache.write() else {
+      tracing::error!("Gateway cache poisoned");
+      return;
+    };
+    let Some(unit) = cache.unit_for(id) else {
+      return;
+    };
+
+    let unit = cache.units.entry(unit).or_insert_with(|| Unit {
+      id: id.to_owned(),
+      timestamp,
+      registers: HashMap::new(),
+    });
+    unit.timestamp = unit.timestamp.max(timestamp);
+    for value in values {
+      let kind = kinds
+        .iter()
+        .find(|kind| kind.name == value.name && kind.address == value.address);
+      let Some(kind) = kind else {
+        continue;
+      };
+
+      let words = kind.storage.encode_value(&value.storage);
+      for (address, word) in (kind.address..=Address::MAX).zip(words) {
+        unit.registers.insert((kind.function, address), word);
+      }
+    }
+  }
+}
+
+impl Cache {
+  // NOTE: units are only ever configured so a scada always finds the same
+  // device behind the same unit across restarts and reconfigures
+  fn unit_for(&mut self, id: &str) -> Option<u8> {
+    if let Some(unit) = self.ids.get(id) {
+      return Some(*unit);
+    }
+
+    if self.unmapped.insert(id.to_owned()) {
+      tracing::warn!("No gateway unit configured for {}", id);
+    }
+
+    None
+  }
+}
+
+impl Server {
+  fn respond(&self, request: SlaveRequest<'static>) -> Response {
+    let SlaveRequest { slave, request } = request;
+    let function = function_code(&request);
+
+    let response = match request {
+      Request::ReadHoldingRegisters(address, quantity) => self
+        .read(slave, Function::HoldingRegisters, address, quantity)
+        .map(Response::ReadHoldingRegisters),
+      Request::ReadInputRegisters(address, quantity) => self
+        .read(slave, Function::InputRegisters, address, quantity)
+        .map(Response::ReadInputRegisters),
+      Request::ReadCoils(address, quantity) => self
+        .read(slave, Function::Coils, address, quantity)
+        .map(|words| Response::ReadCoils(words_to_bits(words))),
+      Request::ReadDiscreteInputs(address, quantity) => self
+        .read(slave, Function::DiscreteInputs, address, quantity)
+        .map(|words| Response::ReadDiscreteInputs(words_to_bits(words))),
+      _ => Err(ExceptionCode::IllegalFunction),
+    };
+
+    response.unwrap_or_else(|code| exception(function, code))
+  }
+
+  fn read(
+    &self,
+    slave: u8,
+    function: Function,
+    address: Address,
+    quantity: Quantity,
+  ) -> Result<Vec<u16>, ExceptionCode> {
+    if quantity == 0 || quantity > max_quantity_for(function) {
+      return Err(ExceptionCode::IllegalDataValue);
+    }
+
+    let cache = self
+      .cache
+      .read()
+      .map_err(|_| ExceptionCode::ServerDeviceFailure)?;
+    let unit = cache
+      .units
+      .get(&slave)
+      .ok_or(ExceptionCode::GatewayPathUnavailable)?;
+    if chrono::Utc::now().signed_duration_since(unit.timestamp)
+      > cache.stale_timeout
+    {
+      tracing::debug!("Gateway unit {} of {} is stale", slave, unit.id);
+      return Err(ExceptionCode::GatewayTargetDevice);
+    }
+
+    (0..quantity)
+      .map(|offset| {
+        address
+          .checked_add(offset)
+          .and_then(|address| unit.registers.get(&(function, address)))
+          .copied()
+      })
+      .collect::<Option<Vec<_>>>()
+      .ok_or(ExceptionCode::IllegalDataAddress)
+  }
+}
+
+impl tokio_modbus::server::Service for Server {
+  type Request = SlaveRequest<'static>;
+  type Response = Response;
+  type Error = std::io::Error;
+  type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+  fn call(&self, request: Self::Request) -> Self::Future {
+    std::future::ready(Ok(self.respond(request)))
+  }
+}
@@ -1,11 +1,12 @@
 pub mod cloud;
 pub mod db;
+pub mod gateway;
 pub mod i2c;
 pub mod modbus;
 pub mod net;
 pub mod serial;
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::*;
 
@@ -13,9 +14,10 @@ pub(crate) trait Service {
   fn new(config: config::Values) -> Self;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Values {
   db: db::Service,
+  gateway: gateway::Service,
   cloud: cloud::Service,
   modbus: modbus::Service,
   net: net::Service,
@@ -25,50 +27,112 @@ struct Values {
 
 #[derive(Debug, Clone)]
 pub(crate) struct Container {
-  values: Arc<Values>,
+  values: Arc<RwLock<Arc<Values>>>,
 }
 
 impl Container {
   pub(crate) fn new(config: config::Values) -> Self {
     Self {
-      values: Arc::new(Values {
+      values: Arc::new(RwLock::new(Arc::new(Values {
         db: db::Service::new(config.clone()),
+        gateway: gateway::Service::new(config.clone()),
         cloud: cloud::Service::new(config.clone()),
         modbus: modbus::Service::new(config.clone()),
         net: net::Service::new(config.clone()),
         i2c: i2c::Service::new(config.clone()),
         serial: serial::Service::new(config.clone()),
-      }),
+      }))),
+    }
+  }
+
+  // NOTE: services that didn't change are kept as is so that db pools and
+  // http clients survive and modbus keeps its bound devices
+  #[tracing::instrument(skip(self, config))]
+  pub(crate) async fn reconfigure(
+    &self,
+    config: config::Values,
+    changes: &[String],
+  ) {
+    let current = self.values();
+    let mut values = (*current).clone();
+
+    if config::changed(changes, "db") {
+      tracing::info!("Reconfiguring db service");
+      values.db = db::Service::new(config.clone());
+    }
+    if config::changed(changes, "cloud") {
+      tracing::info!("Reconfiguring cloud service");
+      values.cloud = cloud::Service::new(config.clone());
+    }
+    if config::changed(changes, "modbus") {
+      tracing::info!("Reconfiguring modbus service");
+      values.modbus = current.modbus.reconfigure(config.clone()).await;
+    }
+    if config::changed(changes, "network") {
+      tracing::info!("Reconfiguring network service");
+      values.net = net::Service::new(config.clone());
+    }
+    if config::changed(changes, "hardware") {
+      tracing::info!("Reconfiguring i2c service");
+      values.i2c = i2c::Service::new(config.clone());
     }
+    if config::changed(changes, "serial") {
+      tracing::info!("Reconfiguring serial service");
+      values.serial = serial::Service::new(config.clone());
+    }
+    if config::changed(changes, "gateway") {
+      tracing::info!("Reconfiguring gateway service");
+      values.gateway = current.gateway.reconfigure(config).await;
+    }
+
+    let mut lock = match self.values.write() {
+      Ok(lock) => lock,
+      Err(poisoned) => poisoned.into_inner(),
+    };
+    *lock = Arc::new(values);
+  }
+
+  // NOTE: callers get a snapshot so a reconfiguration never swaps a
+  // service out from under a running process
+  fn values(&self) -> Arc<Values> {
+    match self.values.read() {
+      Ok(values) => values.clone(),
+      Err(poisoned) => poisoned.into_inner().clone(),
+    }
+  }
+
+  #[inline]
+  pub(crate) fn db(&self) -> db::Service {
+    self.values().db.clone()
   }
 
   #[inline]
-  pub(crate) fn db(&self) -> &db::Service {
-    &self.values.db
+  pub(crate) fn gateway(&self) -> gateway::Service {
+    self.values().gateway.clone()
   }
 
   #[inline]
-  pub(crate) fn cloud(&self) -> &cloud::Service {
-    &self.values.cloud
+  pub(crate) fn cloud(&self) -> cloud::Service {
+    self.values().cloud.clone()
   }
 
   #[inline]
-  pub(crate) fn modbus(&self) -> &modbus::Service {
-    &self.values.modbus
+  pub(crate) fn modbus(&self) -> modbus::Service {
+    self.values().modbus.clone()
   }
 
   #[inline]
-  pub(crate) fn net(&self) -> &net::Service {
-    &self.values.net
+  pub(crate) fn net(&self) -> net::Service {
+    self.values().net.clone()
   }
 
   #[inline]
-  pub(crate) fn i2c(&self) -> &i2c::Service {
-    &self.values.i2c
+  pub(crate) fn i2c(&self) -> i2c::Service {
+    self.values().i2c.clone()
   }
 
   #[inline]
-  pub(crate) fn serial(&self) -> &serial::Service {
-    &self.values.serial
+  pub(crate) fn serial(&self) -> serial::Service {
+    self.values().serial.clone()
   }
 }
@@ -5,13 +5,49 @@ use super::span::*;
 // NITPICK: better error handling for span batching
 // NITPICK: macros to implement batch parsing
 
+// NOTE: protocol limits for a single read request
+pub(crate) const MAX_REGISTER_QUANTITY: Quantity = 125;
+pub(crate) const MAX_BIT_QUANTITY: Quantity = 2000;
+
+pub(crate) fn max_quantity_for(function: Function) -> Quantity {
+  match function {
+    Function::HoldingRegisters
+    | Function::InputRegisters
+    | Function::DeviceIdentification => MAX_REGISTER_QUANTITY,
+    Function::Coils | Function::DiscreteInputs => MAX_BIT_QUANTITY,
+  }
+}
+
+// NOTE: protocol limit for a single write multiple registers request
+pub(crate) const MAX_WRITE_REGISTER_QUANTITY: Quantity = 123;
+
 #[derive(Clone, Debug)]
 pub(crate) struct Batch<TSpan: Span> {
   pub(crate) address: Address,
   pub(crate) quantity: Quantity,
+  pub(crate) function: Function,
   pub(crate) inner: Vec<TSpan>,
 }
 
+impl<TSpan: Span> Batch<TSpan> {
+  pub(crate) fn simplify(&self) -> Batch<SimpleSpan> {
+    Batch::<SimpleSpan> {
+      address: self.address,
+      quantity: self.quantity,
+      function: self.function,
+      inner: self
+        .inner
+        .iter()
+        .map(|span| SimpleSpan {
+          address: span.address(),
+          quantity: span.quantity(),
+          function: span.function(),
+        })
+        .collect(),
+    }
+  }
+}
+
 impl<TSpan: Span> Span for Batch<TSpan> {
   fn address(&self) -> Address {
     self.address
@@ -20,6 +56,10 @@ impl<TSpan: Span> Span for Batch<TSpan> {
   fn quantity(&self) -> Quantity {
     self.quantity
   }
+
+  fn function(&self) -> Function {
+    self.function
+  }
 }
 
 impl<TSpan: Span> Span for &Batch<TSpan> {
@@ -30,6 +70,10 @@ impl<TSpan: Span> Span for &Batch<TSpan> {
   fn quantity(&self) -> Quantity {
     self.quantity
   }
+
+  fn function(&self) -> Function {
+    self.function
+  }
 }
 
 macro_rules! parse_batch {
@@ -65,6 +109,7 @@ macro_rules! parse_batch {
     Ok(Batch::<TSpan> {
       address: $self.address,
       quantity: $self.quantity,
+      function: $self.function,
       inner,
     })
   }};
@@ -110,9 +155,10 @@ pub(crate) fn batch_spans<
 >(
   spans: TIntoIterator,
   threshold: u16,
+  max_quantity: Option<Quantity>,
 ) -> Vec<Batch<TSpan>> {
   let mut spans = spans.into_iter().collect::<Vec<_>>();
-  spans.sort_by_key(|span| span.address());
+  spans.sort_by_key(|span| (span.function(), span.address()));
 
   let mut iter = spans.into_iter();
   let first = match iter.by_ref().next() {
@@ -123,23 +169,44 @@ pub(crate) fn batch_spans<
   let mut current = Batch::<TSpan> {
     address: first.address(),
     quantity: first.quantity(),
+    function: first.function(),
     inner: vec![first],
   };
 
   for span in iter {
+    // NOTE: identification objects are read one by one
+    if span.function() != current.function
+      || current.function == Function::DeviceIdentification
+    {
+      batches.push(current);
+      current = Batch::<TSpan> {
+        address: span.address(),
+        quantity: span.quantity(),
+        function: span.function(),
+        inner: vec![span],
+      };
+      continue;
+    }
+
     #[allow(clippy::unwrap_used, reason = "it should panic")]
     let gap = span
       .address()
       .checked_sub(current.address.checked_add(current.quantity).unwrap())
       .unwrap();
-    if gap < threshold {
-      #[allow(clippy::unwrap_used, reason = "it should panic")]
-      let quantity = span
-        .address()
-        .checked_add(span.quantity())
-        .unwrap()
-        .checked_sub(current.address)
-        .unwrap();
+    #[allow(clippy::unwrap_used, reason = "it should panic")]
+    let quantity = span
+      .address()
+      .checked_add(span.quantity())
+      .unwrap()
+      .checked_sub(current.address)
+      .unwrap();
+    let limit = match max_quantity {
+      Some(max_quantity) => {
+        max_quantity.min(max_quantity_for(current.function))
+      }
+      None => max_quantity_for(current.function),
+    };
+    if gap < threshold && quantity <= limit {
       current.quantity = quantity;
       current.inner.push(span);
     } else {
@@ -147,6 +214,7 @@ pub(crate) fn batch_spans<
       current = Batch::<TSpan> {
         address: span.address(),
         quantity: span.quantity(),
+        function: span.function(),
         inner: vec![span],
       }
     }
@@ -155,3 +223,74 @@ pub(crate) fn batch_spans<
 
   batches
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn spans(
+    function: Function,
+    quantity: Quantity,
+    count: u16,
+  ) -> Vec<SimpleSpan> {
+    (0..count)
+      .map(|index| SimpleSpan {
+        address: index.saturating_mul(quantity),
+        quantity,
+        function,
+      })
+      .collect()
+  }
+
+  fn layout(batches: &[Batch<SimpleSpan>]) -> Vec<(Address, Quantity)> {
+    batches
+      .iter()
+      .map(|batch| (batch.address, batch.quantity))
+      .collect()
+  }
+
+  #[test]
+  fn contiguous_registers_split_at_protocol_limit() {
+    let batches =
+      batch_spans(spans(Function::HoldingRegisters, 25, 8), 10, None);
+    assert_eq!(layout(&batches), vec![(0, 125), (125, 75)]);
+    assert_eq!(batches.first().map(|batch| batch.inner.len()), Some(5));
+  }
+
+  #[test]
+  fn smaller_device_limit_wins() {
+    let batches =
+      batch_spans(spans(Function::InputRegisters, 25, 8), 10, Some(50));
+    assert_eq!(
+      layout(&batches),
+      vec![(0, 50), (50, 50), (100, 50), (150, 50)]
+    );
+
+    let batches =
+      batch_spans(spans(Function::InputRegisters, 25, 8), 10, Some(500));
+    assert_eq!(layout(&batches), vec![(0, 125), (125, 75)]);
+  }
+
+  #[test]
+  fn bits_split_at_bit_limit() {
+    let batches = batch_spans(spans(Function::Coils, 500, 5), 10, None);
+    assert_eq!(layout(&batches), vec![(0, 2000), (2000, 500)]);
+  }
+
+  #[test]
+  fn functions_never_share_batches() {
+    let mut mixed = spans(Function::HoldingRegisters, 10, 2);
+    mixed.extend(spans(Function::InputRegisters, 10, 2));
+    let batches = batch_spans(mixed, 10, None);
+    assert_eq!(
+      batches
+        .iter()
+        .map(|batch| (batch.function, batch.address, batch.quantity))
+        .collect::<Vec<_>>(),
+      vec![
+        (Function::HoldingRegisters, 0, 20),
+        (Function::InputRegisters, 0, 20),
+      ]
+    );
+  }
+}
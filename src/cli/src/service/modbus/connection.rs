@@ -1,17 +1,23 @@
+use std::collections::HashMap;
+
 use futures_time::future::FutureExt;
 use thiserror::Error;
 use tokio::net::TcpStream;
 use tokio_modbus::{
-  client::{Context, Writer},
+  client::{Client, Context, Writer},
   prelude::Reader,
   slave::SlaveContext,
-  Slave,
+  Request, Response, Slave,
 };
 use tokio_serial::SerialPortBuilderExt;
 
-use super::{record::SimpleRecord, span::SimpleSpan};
+use super::{
+  identification,
+  record::SimpleRecord,
+  span::{Function, SimpleSpan},
+};
 
-#[expect(
+#[allow(
   clippy::allow_attributes_without_reason,
   reason = "generated by derivative"
 )]
@@ -19,21 +25,50 @@ mod __device {
   use derivative::Derivative;
   use std::net::SocketAddr;
 
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  pub(crate) struct Line {
+    pub(crate) baud_rate: u32,
+    pub(crate) parity: tokio_serial::Parity,
+    pub(crate) stop_bits: tokio_serial::StopBits,
+    pub(crate) data_bits: tokio_serial::DataBits,
+  }
+
+  impl Default for Line {
+    fn default() -> Self {
+      Self {
+        baud_rate: 38400,
+        parity: tokio_serial::Parity::None,
+        stop_bits: tokio_serial::StopBits::One,
+        data_bits: tokio_serial::DataBits::Eight,
+      }
+    }
+  }
+
   #[derive(Derivative)]
   #[derivative(Clone, Debug, Hash, Eq, PartialEq)]
   pub(crate) enum Device {
     Tcp(SocketAddr),
+    RtuOverTcp(SocketAddr),
+    Udp(SocketAddr),
     Rtu {
       path: String,
       // NOTE: Eq is just a marker trait and works through PartialEq
       #[derivative(Hash = "ignore", PartialEq = "ignore")]
-      baud_rate: u32,
+      line: Line,
     },
   }
 }
 
 pub(crate) use __device::*;
 
+impl Device {
+  // NOTE: tokio-modbus 0.9 can't frame device identification responses
+  // over rtu so only tcp and udp devices can be identified
+  pub(crate) fn supports_identification(&self) -> bool {
+    matches!(self, Device::Tcp(_) | Device::Udp(_))
+  }
+}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub(crate) struct Destination {
   pub(crate) device: Device,
@@ -73,14 +108,81 @@ pub(crate) struct Connection {
   device: Device,
   ctx: Option<Context>,
   slave: Option<u8>,
+  bus: Option<Bus>,
+  stale: bool,
+  desyncs: u64,
+}
+
+// NOTE: slave -> turnaround delay for kinds that need more time than the
+// default before the next slave on the bus can be addressed
+pub(crate) type Turnarounds =
+  std::sync::Arc<std::sync::RwLock<HashMap<u8, tokio::time::Duration>>>;
+
+#[derive(Debug, Clone)]
+pub(crate) struct BusConfig {
+  pub(crate) turnaround: tokio::time::Duration,
+  pub(crate) turnarounds: Turnarounds,
+  pub(crate) rtu_over_tcp_line: Line,
+}
+
+// NOTE: rtu slaves share one line so we have to keep it silent between frames
+#[derive(Debug)]
+struct Bus {
+  gap: tokio::time::Duration,
+  turnaround: tokio::time::Duration,
+  turnarounds: Turnarounds,
+  idle: Option<tokio::time::Instant>,
+  slave: Option<u8>,
+}
+
+impl Bus {
+  fn turnaround_for(&self, slave: u8) -> Option<tokio::time::Duration> {
+    match self.turnarounds.read() {
+      Ok(turnarounds) => turnarounds.get(&slave).copied(),
+      Err(poisoned) => poisoned.into_inner().get(&slave).copied(),
+    }
+  }
 }
 
 impl Connection {
-  pub(crate) fn new(device: Device) -> Self {
+  pub(crate) fn new(device: Device, config: &BusConfig) -> Self {
+    // NOTE: rtu over tcp gateways put our frames on a line we can't see
+    // so we keep the gap of the line they are configured with
+    let line = match &device {
+      Device::Rtu { line, .. } => Some(*line),
+      Device::RtuOverTcp(_) => Some(config.rtu_over_tcp_line),
+      Device::Tcp(_) | Device::Udp(_) => None,
+    };
+    let bus = line.map(|line| Bus {
+      gap: inter_frame_gap(&line),
+      turnaround: config.turnaround,
+      turnarounds: config.turnarounds.clone(),
+      idle: None,
+      slave: None,
+    });
+
     Self {
       device,
       ctx: None,
       slave: None,
+      bus,
+      stale: false,
+      desyncs: 0,
+    }
+  }
+
+  // NOTE: resets the counter so it can be added to per iteration metrics
+  pub(crate) fn take_desyncs(&mut self) -> u64 {
+    std::mem::take(&mut self.desyncs)
+  }
+
+  // NOTE: responses to timed out requests can still arrive later and would
+  // get matched to the next request so we drop the context to discard them
+  fn discard_stale(&mut self) {
+    if self.stale {
+      tracing::trace!("Discarding stale connection");
+      self.ctx = None;
+      self.stale = false;
     }
   }
 
@@ -88,12 +190,123 @@ impl Connection {
     &mut self,
     slave: Option<u8>,
   ) -> Result<(), ConnectError> {
-    if self.ctx.is_none() || self.slave != slave {
+    if self.ctx.is_none() || self.needs_reconnect(slave) {
       let _ = self.reconnect(slave).await?;
     }
 
     Ok(())
   }
+
+  // NOTE: bus slaves are addressed per frame so switching them is free
+  fn needs_reconnect(&self, slave: Option<u8>) -> bool {
+    self.bus.is_none() && self.slave != slave
+  }
+
+  // NOTE: switching slaves waits out the turnaround of the previous one
+  // because slow slaves keep driving the line after they respond
+  async fn wait_for_bus(&mut self, slave: Option<u8>) {
+    let Some(bus) = &self.bus else {
+      return;
+    };
+
+    let turnaround = match bus.slave {
+      Some(previous) if Some(previous) != slave => bus.turnaround_for(previous),
+      _ => None,
+    };
+    let idle = match (bus.idle, turnaround) {
+      (Some(idle), Some(turnaround)) => idle.checked_add(turnaround),
+      (idle, _) => idle,
+    };
+    if let Some(idle) = idle {
+      tokio::time::sleep_until(idle).await;
+    }
+  }
+
+  fn release_bus(&mut self, slave: Option<u8>) {
+    if let Some(bus) = &mut self.bus {
+      bus.idle = tokio::time::Instant::now().checked_add(bus.gap);
+      bus.slave = slave;
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub(crate) enum ExceptionCode {
+  IllegalFunction,
+  IllegalDataAddress,
+  IllegalDataValue,
+  ServerDeviceFailure,
+  Acknowledge,
+  ServerDeviceBusy,
+  MemoryParityError,
+  GatewayPathUnavailable,
+  GatewayTargetDevice,
+}
+
+impl ExceptionCode {
+  pub(crate) fn code(self) -> u8 {
+    match self {
+      ExceptionCode::IllegalFunction => 0x01,
+      ExceptionCode::IllegalDataAddress => 0x02,
+      ExceptionCode::IllegalDataValue => 0x03,
+      ExceptionCode::ServerDeviceFailure => 0x04,
+      ExceptionCode::Acknowledge => 0x05,
+      ExceptionCode::ServerDeviceBusy => 0x06,
+      ExceptionCode::MemoryParityError => 0x08,
+      ExceptionCode::GatewayPathUnavailable => 0x0A,
+      ExceptionCode::GatewayTargetDevice => 0x0B,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Error)]
+#[error("Function {function} responded with exception {code:?}")]
+pub(crate) struct Exception {
+  pub(crate) function: u8,
+  pub(crate) code: ExceptionCode,
+}
+
+impl Exception {
+  // NOTE: tokio-modbus 0.9 doesn't export its exception response so we
+  // recover it from the message of the io error it gets wrapped in
+  pub(crate) fn from_io_error(error: &std::io::Error) -> Option<Self> {
+    if error.kind() != std::io::ErrorKind::Other {
+      return None;
+    }
+
+    let message = error.get_ref()?.to_string();
+    let (function, description) =
+      message.strip_prefix("Modbus function ")?.split_once(": ")?;
+    let code = match description {
+      "Illegal function" => ExceptionCode::IllegalFunction,
+      "Illegal data address" => ExceptionCode::IllegalDataAddress,
+      "Illegal data value" => ExceptionCode::IllegalDataValue,
+      "Server device failure" => ExceptionCode::ServerDeviceFailure,
+      "Acknowledge" => ExceptionCode::Acknowledge,
+      "Server device busy" => ExceptionCode::ServerDeviceBusy,
+      "Memory parity error" => ExceptionCode::MemoryParityError,
+      "Gateway path unavailable" => ExceptionCode::GatewayPathUnavailable,
+      "Gateway target device failed to respond" => {
+        ExceptionCode::GatewayTargetDevice
+      }
+      _ => return None,
+    };
+
+    Some(Self {
+      function: function.parse().ok()?,
+      code,
+    })
+  }
+
+  pub(crate) fn is_transient(&self) -> bool {
+    matches!(
+      self.code,
+      ExceptionCode::Acknowledge
+        | ExceptionCode::ServerDeviceBusy
+        | ExceptionCode::GatewayPathUnavailable
+        | ExceptionCode::GatewayTargetDevice
+    )
+  }
 }
 
 #[derive(Debug, Error)]
@@ -104,6 +317,12 @@ pub(crate) enum ReadError {
   #[error("Failed reading")]
   Read(std::io::Error),
 
+  #[error("Response did not match the request")]
+  Desync(std::io::Error),
+
+  #[error("Device responded with an exception")]
+  Exception(Exception),
+
   #[error("Connection timed out")]
   Timeout(std::io::Error),
 }
@@ -116,6 +335,12 @@ pub(crate) enum WriteError {
   #[error("Failed reading")]
   Read(std::io::Error),
 
+  #[error("Response did not match the request")]
+  Desync(std::io::Error),
+
+  #[error("Device responded with an exception")]
+  Exception(Exception),
+
   #[error("Connection timed out")]
   Timeout(std::io::Error),
 }
@@ -128,9 +353,14 @@ impl Connection {
     span: SimpleSpan,
     timeout: chrono::Duration,
   ) -> Result<ReadResponse, ReadError> {
-    let response = self
-      .simple_read_impl(slave, span, timeout_from_chrono(timeout))
-      .await?;
+    let timeout = timeout_from_chrono(timeout);
+    let response = match self.simple_read_impl(slave, span, timeout).await {
+      Err(ReadError::Desync(error)) => {
+        tracing::debug!("Retrying read after desync {}", error);
+        self.simple_read_impl(slave, span, timeout).await?
+      }
+      response => response?,
+    };
 
     tracing::trace!("Simple read successful");
 
@@ -144,9 +374,14 @@ impl Connection {
     record: SimpleRecord,
     timeout: chrono::Duration,
   ) -> Result<WriteResponse, WriteError> {
-    self
-      .simple_write_impl(slave, record, timeout_from_chrono(timeout))
-      .await?;
+    let timeout = timeout_from_chrono(timeout);
+    match self.simple_write_impl(slave, record.clone(), timeout).await {
+      Err(WriteError::Desync(error)) => {
+        tracing::debug!("Retrying write after desync {}", error);
+        self.simple_write_impl(slave, record, timeout).await?
+      }
+      response => response?,
+    };
 
     tracing::trace!("Simple read successful");
 
@@ -159,9 +394,22 @@ impl Connection {
     span: SimpleSpan,
     timeout: futures_time::time::Duration,
   ) -> Result<ReadResponse, ReadError> {
+    // NOTE: answer for the device instead of garbling the line
+    if span.function == Function::DeviceIdentification
+      && !self.device.supports_identification()
+    {
+      return Err(ReadError::Exception(Exception {
+        function: identification::FUNCTION_CODE,
+        code: ExceptionCode::IllegalFunction,
+      }));
+    }
+
+    self.discard_stale();
+    self.wait_for_bus(slave).await;
+    let needs_reconnect = self.needs_reconnect(slave);
     let response = match &mut self.ctx {
       Some(ctx) => {
-        let ctx = if self.slave != slave {
+        let ctx = if needs_reconnect {
           self.reconnect(slave).await?
         } else {
           ctx
@@ -173,9 +421,20 @@ impl Connection {
         Self::simple_read_impl_connected(ctx, slave, span, timeout).await
       }
     };
+    self.release_bus(slave);
 
-    if matches!(response, Err(ReadError::Connection(_) | ReadError::Read(_))) {
-      self.ctx = None;
+    match &response {
+      Err(ReadError::Connection(_) | ReadError::Read(_)) => {
+        self.ctx = None;
+      }
+      Err(ReadError::Desync(_)) => {
+        self.ctx = None;
+        self.desyncs = self.desyncs.saturating_add(1);
+      }
+      Err(ReadError::Timeout(_)) => {
+        self.stale = true;
+      }
+      _ => {}
     }
 
     response
@@ -187,26 +446,55 @@ impl Connection {
     record: SimpleRecord,
     timeout: futures_time::time::Duration,
   ) -> Result<WriteResponse, WriteError> {
+    self.discard_stale();
+    self.wait_for_bus(slave).await;
+    let needs_reconnect = self.needs_reconnect(slave);
+    let broadcast = match &self.bus {
+      Some(bus) if slave == Some(BROADCAST_SLAVE) => Some(bus.turnaround),
+      _ => None,
+    };
     let response = match &mut self.ctx {
       Some(ctx) => {
-        let ctx = if self.slave != slave {
+        let ctx = if needs_reconnect {
           self.reconnect(slave).await?
         } else {
           ctx
         };
-        Self::simple_write_impl_connected(ctx, slave, record, timeout).await
+        match broadcast {
+          Some(turnaround) => {
+            Self::broadcast_write_impl_connected(ctx, record, turnaround).await
+          }
+          None => {
+            Self::simple_write_impl_connected(ctx, slave, record, timeout).await
+          }
+        }
       }
       None => {
         let ctx = self.reconnect(slave).await?;
-        Self::simple_write_impl_connected(ctx, slave, record, timeout).await
+        match broadcast {
+          Some(turnaround) => {
+            Self::broadcast_write_impl_connected(ctx, record, turnaround).await
+          }
+          None => {
+            Self::simple_write_impl_connected(ctx, slave, record, timeout).await
+          }
+        }
       }
     };
+    self.release_bus(slave);
 
-    if matches!(
-      response,
-      Err(WriteError::Connection(_) | WriteError::Read(_))
-    ) {
-      self.ctx = None;
+    match &response {
+      Err(WriteError::Connection(_) | WriteError::Read(_)) => {
+        self.ctx = None;
+      }
+      Err(WriteError::Desync(_)) => {
+        self.ctx = None;
+        self.desyncs = self.desyncs.saturating_add(1);
+      }
+      Err(WriteError::Timeout(_)) => {
+        self.stale = true;
+      }
+      _ => {}
     }
 
     response
@@ -228,13 +516,53 @@ impl Connection {
       ctx.set_slave(Slave::tcp_device())
     }
 
-    match ctx
-      .read_holding_registers(span.address, span.quantity)
-      .timeout(timeout)
-      .await
-    {
+    let response = match span.function {
+      Function::HoldingRegisters => {
+        ctx
+          .read_holding_registers(span.address, span.quantity)
+          .timeout(timeout)
+          .await
+      }
+      Function::InputRegisters => {
+        ctx
+          .read_input_registers(span.address, span.quantity)
+          .timeout(timeout)
+          .await
+      }
+      Function::Coils => ctx
+        .read_coils(span.address, span.quantity)
+        .timeout(timeout)
+        .await
+        .map(|response| response.map(bits_to_words)),
+      Function::DiscreteInputs => ctx
+        .read_discrete_inputs(span.address, span.quantity)
+        .timeout(timeout)
+        .await
+        .map(|response| response.map(bits_to_words)),
+      Function::DeviceIdentification => ctx
+        .call(Request::Custom(
+          identification::FUNCTION_CODE,
+          identification::request(span.address).into(),
+        ))
+        .timeout(timeout)
+        .await
+        .map(|response| {
+          response
+            .and_then(|response| parse_identification_response(span, response))
+        }),
+    };
+
+    match response {
       Err(timeout_error) => Err(ReadError::Timeout(timeout_error)),
-      Ok(Err(connection_error)) => Err(ReadError::Read(connection_error)),
+      Ok(Err(connection_error)) => {
+        match Exception::from_io_error(&connection_error) {
+          Some(exception) => Err(ReadError::Exception(exception)),
+          None if is_desync(&connection_error) => {
+            Err(ReadError::Desync(connection_error))
+          }
+          None => Err(ReadError::Read(connection_error)),
+        }
+      }
       Ok(Ok(response)) => Ok(response),
     }
   }
@@ -261,12 +589,41 @@ impl Connection {
       .await
     {
       Err(timeout_error) => Err(WriteError::Timeout(timeout_error)),
-      Ok(Err(connection_error)) => Err(WriteError::Read(connection_error)),
+      Ok(Err(connection_error)) => {
+        match Exception::from_io_error(&connection_error) {
+          Some(exception) => Err(WriteError::Exception(exception)),
+          None if is_desync(&connection_error) => {
+            Err(WriteError::Desync(connection_error))
+          }
+          None => Err(WriteError::Read(connection_error)),
+        }
+      }
       Ok(Ok(_)) => Ok(()),
     }
   }
 }
 
+impl Connection {
+  // NOTE: slaves never respond to broadcasts so instead of a response
+  // we wait out the turnaround delay for them to process the request
+  async fn broadcast_write_impl_connected(
+    ctx: &mut Context,
+    record: SimpleRecord,
+    turnaround: tokio::time::Duration,
+  ) -> Result<WriteResponse, WriteError> {
+    ctx.set_slave(Slave::broadcast());
+
+    match ctx
+      .write_multiple_registers(record.address, &record.values)
+      .timeout(futures_time::time::Duration::from(turnaround))
+      .await
+    {
+      Err(_) | Ok(Ok(_)) => Ok(()),
+      Ok(Err(connection_error)) => Err(WriteError::Read(connection_error)),
+    }
+  }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum ConnectError {
   #[error("Failed to connect TCP")]
@@ -275,6 +632,9 @@ pub(crate) enum ConnectError {
   #[error("Failed to connect RTU")]
   RtuConnect(#[from] serialport::Error),
 
+  #[error("Failed to connect UDP")]
+  UdpConnect(std::io::Error),
+
   #[error("Wrong slave number")]
   Slave,
 }
@@ -294,8 +654,22 @@ impl Connection {
         let stream = TcpStream::connect(socket).await?;
         tokio_modbus::prelude::tcp::attach(stream)
       }
-      Device::Rtu { path, baud_rate } => {
-        let stream = tokio_serial::new(path, *baud_rate).open_native_async()?;
+      Device::RtuOverTcp(socket) => {
+        let stream = TcpStream::connect(socket).await?;
+        tokio_modbus::prelude::rtu::attach(stream)
+      }
+      Device::Udp(socket) => {
+        let stream = super::udp::UdpStream::connect(*socket)
+          .await
+          .map_err(ConnectError::UdpConnect)?;
+        tokio_modbus::prelude::tcp::attach(stream)
+      }
+      Device::Rtu { path, line } => {
+        let stream = tokio_serial::new(path, line.baud_rate)
+          .parity(line.parity)
+          .stop_bits(line.stop_bits)
+          .data_bits(line.data_bits)
+          .open_native_async()?;
         tokio_modbus::prelude::rtu::attach(stream)
       }
     };
@@ -315,6 +689,73 @@ impl Connection {
   }
 }
 
+const BROADCAST_SLAVE: u8 = 0;
+
+// NOTE: tokio-modbus reports mismatched response headers only through the message
+fn is_desync(error: &std::io::Error) -> bool {
+  error.kind() == std::io::ErrorKind::InvalidData
+    && error.to_string().starts_with("Invalid response header")
+}
+
+// NOTE: 3.5 characters of silence with a fixed 1.75ms above 19200 baud
+// as recommended by the modbus over serial line specification
+fn inter_frame_gap(line: &Line) -> tokio::time::Duration {
+  const FIXED_GAP: tokio::time::Duration =
+    tokio::time::Duration::from_micros(1750);
+
+  if line.baud_rate > 19200 {
+    return FIXED_GAP;
+  }
+
+  let data_bits: u64 = match line.data_bits {
+    tokio_serial::DataBits::Five => 5,
+    tokio_serial::DataBits::Six => 6,
+    tokio_serial::DataBits::Seven => 7,
+    tokio_serial::DataBits::Eight => 8,
+  };
+  let parity_bits: u64 = match line.parity {
+    tokio_serial::Parity::None => 0,
+    tokio_serial::Parity::Odd | tokio_serial::Parity::Even => 1,
+  };
+  let stop_bits: u64 = match line.stop_bits {
+    tokio_serial::StopBits::One => 1,
+    tokio_serial::StopBits::Two => 2,
+  };
+  let character_bits = data_bits
+    .saturating_add(parity_bits)
+    .saturating_add(stop_bits)
+    .saturating_add(1);
+
+  character_bits
+    .saturating_mul(3_500_000)
+    .checked_div(u64::from(line.baud_rate))
+    .map_or(FIXED_GAP, tokio::time::Duration::from_micros)
+}
+
+fn parse_identification_response(
+  span: SimpleSpan,
+  response: Response,
+) -> Result<ReadResponse, std::io::Error> {
+  let object = match response {
+    Response::Custom(identification::FUNCTION_CODE, data) => {
+      identification::parse_response(span.address, &data, span.quantity)
+    }
+    _ => None,
+  };
+
+  object.ok_or_else(|| {
+    std::io::Error::new(
+      std::io::ErrorKind::InvalidData,
+      format!("Invalid device identification object {}", span.address),
+    )
+  })
+}
+
+// NOTE: one word per bit so coils and discrete inputs parse like registers
+fn bits_to_words(bits: Vec<bool>) -> ReadResponse {
+  bits.into_iter().map(u16::from).collect()
+}
+
 fn timeout_from_chrono(
   timeout: chrono::Duration,
 ) -> futures_time::time::Duration {
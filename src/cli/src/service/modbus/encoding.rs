@@ -1,80 +1,95 @@
 use itertools::Itertools;
 
-#[cfg(target_endian = "little")]
+// NOTE: decoded bytes are always big endian regardless of host endianness
+
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub(crate) enum Order {
+  // NOTE: ABCD - most significant word first and high byte first
+  #[default]
+  Big,
+
+  // NOTE: DCBA - least significant word first and low byte first
+  Little,
+
+  // NOTE: CDAB - least significant word first and high byte first
+  WordSwapped,
+
+  // NOTE: BADC - most significant word first and low byte first
+  ByteSwapped,
+}
+
+impl Order {
+  fn swaps_words(self) -> bool {
+    matches!(self, Order::Little | Order::WordSwapped)
+  }
+
+  fn swaps_bytes(self) -> bool {
+    matches!(self, Order::Little | Order::ByteSwapped)
+  }
+}
+
 pub(crate) fn decode_numeric_bytes<TIterator, TIntoIterator>(
   data: TIntoIterator,
+  order: Order,
 ) -> Vec<u8>
 where
   TIterator: DoubleEndedIterator<Item = u16>,
   TIntoIterator: IntoIterator<Item = u16, IntoIter = TIterator>,
 {
-  data
+  let words = if order.swaps_words() {
+    data.into_iter().rev().collect::<Vec<_>>()
+  } else {
+    data.into_iter().collect::<Vec<_>>()
+  };
+
+  words
     .into_iter()
-    .rev()
-    .flat_map(|value| [(value & 0xFF) as u8, (value >> 8) as u8])
+    .flat_map(|word| decode_word(word, order))
     .collect()
 }
 
-#[cfg(target_endian = "big")]
-pub(crate) fn decode_numeric_bytes<TIntoIterator>(
+// NOTE: strings are read front to back so word orders only swap the bytes
+// within each register and never reverse the registers themselves
+pub(crate) fn decode_string_bytes<TIntoIterator>(
   data: TIntoIterator,
+  order: Order,
 ) -> Vec<u8>
 where
   TIntoIterator: IntoIterator<Item = u16>,
 {
   data
     .into_iter()
-    .flat_map(|value| [(value & 0xFF) as u8, (value >> 8) as u8])
+    .flat_map(|word| decode_word(word, order))
     .collect()
 }
 
-#[cfg(target_endian = "little")]
-pub(crate) fn decode_string_bytes<TIntoIterator>(data: TIntoIterator) -> Vec<u8>
+pub(crate) fn encode_numeric_bytes<TIntoIterator>(
+  data: TIntoIterator,
+  order: Order,
+) -> Vec<u16>
 where
-  TIntoIterator: IntoIterator<Item = u16>,
+  TIntoIterator: IntoIterator<Item = u8>,
 {
-  data
-    .into_iter()
-    .flat_map(|value| [(value >> 8) as u8, (value & 0xFF) as u8])
-    .collect()
-}
+  let mut words = encode_words(data, order);
 
-#[cfg(target_endian = "big")]
-pub(crate) fn decode_string_bytes<TIntoIterator>(data: TIntoIterator) -> Vec<u8>
-where
-  TIntoIterator: IntoIterator<Item = u16>,
-{
-  data
-    .into_iter()
-    .flat_map(|value| [(value & 0xFF) as u8, (value >> 8) as u8])
-    .collect()
+  if order.swaps_words() {
+    words.reverse();
+  }
+
+  words
 }
 
-#[cfg(target_endian = "little")]
-pub(crate) fn encode_numeric_bytes<TIterator, TIntoIterator>(
+pub(crate) fn encode_string_bytes<TIntoIterator>(
   data: TIntoIterator,
+  order: Order,
 ) -> Vec<u16>
 where
-  TIterator: DoubleEndedIterator<Item = u8>,
-  TIntoIterator: IntoIterator<Item = u8, IntoIter = TIterator>,
+  TIntoIterator: IntoIterator<Item = u8>,
 {
-  data
-    .into_iter()
-    .rev()
-    .chunks(2)
-    .into_iter()
-    .map(|mut chunk| {
-      let first = chunk.next().unwrap_or(0u8);
-      let second = chunk.next().unwrap_or(0u8);
-      u16::from_le_bytes([first, second])
-    })
-    .collect()
+  encode_words(data, order)
 }
 
-#[cfg(target_endian = "big")]
-pub(crate) fn encode_numeric_bytes<TIntoIterator>(
-  data: TIntoIterator,
-) -> Vec<u16>
+fn encode_words<TIntoIterator>(data: TIntoIterator, order: Order) -> Vec<u16>
 where
   TIntoIterator: IntoIterator<Item = u8>,
 {
@@ -85,45 +100,272 @@ where
     .map(|mut chunk| {
       let first = chunk.next().unwrap_or(0u8);
       let second = chunk.next().unwrap_or(0u8);
-      u16::from_le_bytes([first, second])
+      encode_word([first, second], order)
     })
     .collect()
 }
 
-#[cfg(target_endian = "little")]
-pub(crate) fn encode_string_bytes<TIntoIterator>(
-  data: TIntoIterator,
-) -> Vec<u16>
-where
-  TIntoIterator: IntoIterator<Item = u8>,
-{
-  data
-    .into_iter()
-    .chunks(2)
-    .into_iter()
-    .map(|mut chunk| {
-      let first = chunk.next().unwrap_or(0u8);
-      let second = chunk.next().unwrap_or(0u8);
-      u16::from_le_bytes([second, first])
+fn decode_word(word: u16, order: Order) -> [u8; 2] {
+  if order.swaps_bytes() {
+    word.to_le_bytes()
+  } else {
+    word.to_be_bytes()
+  }
+}
+
+fn encode_word(bytes: [u8; 2], order: Order) -> u16 {
+  if order.swaps_bytes() {
+    u16::from_le_bytes(bytes)
+  } else {
+    u16::from_be_bytes(bytes)
+  }
+}
+
+// NOTE: every byte holds two decimal digits with the high nibble first
+pub(crate) fn decode_bcd_bytes(bytes: &[u8]) -> Option<u64> {
+  bytes
+    .iter()
+    .flat_map(|byte| [byte.checked_shr(4).unwrap_or(0), byte & 0x0F])
+    .try_fold(0u64, |value, digit| {
+      if digit > 9 {
+        return None;
+      }
+      value.checked_mul(10)?.checked_add(u64::from(digit))
     })
-    .collect()
 }
 
-#[cfg(target_endian = "big")]
-pub(crate) fn encode_string_bytes<TIntoIterator>(
-  data: TIntoIterator,
-) -> Vec<u16>
-where
-  TIntoIterator: IntoIterator<Item = u8>,
-{
-  data
-    .into_iter()
+// NOTE: values with more digits than fit the width don't encode at all
+// because dropping the high digits would write a different number
+pub(crate) fn encode_bcd_bytes(value: u64, width: usize) -> Option<Vec<u8>> {
+  let mut digits = value.to_string().into_bytes();
+  if digits.len() > width.saturating_mul(2) {
+    return None;
+  }
+  digits.reverse();
+  let mut bytes = digits
     .chunks(2)
-    .into_iter()
-    .map(|mut chunk| {
-      let first = chunk.next().unwrap_or(0u8);
-      let second = chunk.next().unwrap_or(0u8);
-      u16::from_le_bytes([first, second])
+    .map(|chunk| {
+      let low = chunk.first().map_or(0, |digit| digit.saturating_sub(b'0'));
+      let high = chunk.get(1).map_or(0, |digit| digit.saturating_sub(b'0'));
+      high.checked_shl(4).unwrap_or(0) | low
     })
-    .collect()
+    .collect::<Vec<_>>();
+  bytes.resize(width, 0);
+  bytes.reverse();
+  Some(bytes)
+}
+
+// NOTE: schneider DATETIME is four big endian words holding the year,
+// month with weekday and day, hour and minute with the invalid flag
+// and milliseconds in that order
+pub(crate) fn decode_schneider_datetime_bytes(
+  bytes: &[u8],
+) -> Option<chrono::NaiveDateTime> {
+  let [_, year, month, day, hour, minute, millis_high, millis_low] =
+    <[u8; 8]>::try_from(bytes).ok()?;
+  if minute & INVALID_FLAG != 0 {
+    return None;
+  }
+
+  make_naive_date_time(
+    year & 0x7F,
+    month & 0x0F,
+    day & 0x1F,
+    hour & 0x1F,
+    minute & 0x3F,
+    u16::from_be_bytes([millis_high, millis_low]),
+  )
+}
+
+pub(crate) fn encode_schneider_datetime_bytes(
+  date_time: chrono::NaiveDateTime,
+) -> Vec<u8> {
+  let [year, month, day, hour, minute] = date_time_fields(date_time);
+  let [millis_high, millis_low] = date_time_millis(date_time).to_be_bytes();
+  vec![0, year, month, day, hour, minute, millis_high, millis_low]
+}
+
+// NOTE: IEC 870-5-4 binary time is seven bytes starting with little endian
+// milliseconds followed by the minute with the invalid flag, hour,
+// weekday with day, month and year padded to four registers
+pub(crate) fn decode_iec870_time_bytes(
+  bytes: &[u8],
+) -> Option<chrono::NaiveDateTime> {
+  let [millis_low, millis_high, minute, hour, day, month, year, _] =
+    <[u8; 8]>::try_from(bytes).ok()?;
+  if minute & INVALID_FLAG != 0 {
+    return None;
+  }
+
+  make_naive_date_time(
+    year & 0x7F,
+    month & 0x0F,
+    day & 0x1F,
+    hour & 0x1F,
+    minute & 0x3F,
+    u16::from_le_bytes([millis_low, millis_high]),
+  )
+}
+
+pub(crate) fn encode_iec870_time_bytes(
+  date_time: chrono::NaiveDateTime,
+) -> Vec<u8> {
+  let [year, month, day, hour, minute] = date_time_fields(date_time);
+  let [millis_low, millis_high] = date_time_millis(date_time).to_le_bytes();
+  vec![millis_low, millis_high, minute, hour, day, month, year, 0]
+}
+
+const INVALID_FLAG: u8 = 0x80;
+
+const CENTURY: i32 = 2000;
+
+fn make_naive_date_time(
+  year: u8,
+  month: u8,
+  day: u8,
+  hour: u8,
+  minute: u8,
+  millis: u16,
+) -> Option<chrono::NaiveDateTime> {
+  chrono::NaiveDate::from_ymd_opt(
+    CENTURY.checked_add(i32::from(year))?,
+    u32::from(month),
+    u32::from(day),
+  )?
+  .and_hms_milli_opt(
+    u32::from(hour),
+    u32::from(minute),
+    u32::from(millis.checked_div(1000)?),
+    u32::from(millis.checked_rem(1000)?),
+  )
+}
+
+// NOTE: the day byte also carries the weekday in its top three bits
+fn date_time_fields(date_time: chrono::NaiveDateTime) -> [u8; 5] {
+  use chrono::{Datelike, Timelike};
+
+  let field = |value: u32| u8::try_from(value).unwrap_or(0);
+  let year = date_time
+    .year()
+    .checked_sub(CENTURY)
+    .and_then(|year| u8::try_from(year).ok())
+    .unwrap_or(0);
+  let weekday = field(date_time.weekday().number_from_monday());
+  [
+    year,
+    field(date_time.month()),
+    weekday.checked_shl(5).unwrap_or(0) | field(date_time.day()),
+    field(date_time.hour()),
+    field(date_time.minute()),
+  ]
+}
+
+fn date_time_millis(date_time: chrono::NaiveDateTime) -> u16 {
+  use chrono::Timelike;
+
+  let millis = date_time
+    .second()
+    .saturating_mul(1000)
+    .saturating_add(date_time.nanosecond().checked_div(1_000_000).unwrap_or(0));
+  u16::try_from(millis).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[allow(clippy::unwrap_used, reason = "test dates are valid")]
+  fn date_time() -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(2024, 2, 29)
+      .unwrap()
+      .and_hms_milli_opt(23, 59, 58, 765)
+      .unwrap()
+  }
+
+  const ORDERED_WORDS: [(Order, [u16; 2]); 4] = [
+    (Order::Big, [0x1234, 0x5678]),
+    (Order::WordSwapped, [0x5678, 0x1234]),
+    (Order::ByteSwapped, [0x3412, 0x7856]),
+    (Order::Little, [0x7856, 0x3412]),
+  ];
+
+  #[test]
+  fn numbers_decode_in_every_order() {
+    for (order, words) in ORDERED_WORDS {
+      assert_eq!(
+        decode_numeric_bytes(words, order),
+        vec![0x12, 0x34, 0x56, 0x78],
+        "{order:?}"
+      );
+      assert_eq!(
+        encode_numeric_bytes([0x12, 0x34, 0x56, 0x78], order),
+        words.to_vec(),
+        "{order:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn strings_never_reverse_words() {
+    let cases = [
+      (Order::Big, [0x4142, 0x4344]),
+      (Order::WordSwapped, [0x4142, 0x4344]),
+      (Order::ByteSwapped, [0x4241, 0x4443]),
+      (Order::Little, [0x4241, 0x4443]),
+    ];
+    for (order, words) in cases {
+      assert_eq!(decode_string_bytes(words, order), b"ABCD", "{order:?}");
+      assert_eq!(
+        encode_string_bytes(*b"ABCD", order),
+        words.to_vec(),
+        "{order:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn bcd_round_trips() {
+    for (value, width) in [(0, 2), (1234, 2), (9999, 2), (12_345_678, 4)] {
+      let bytes = encode_bcd_bytes(value, width);
+      assert_eq!(
+        bytes.as_deref().and_then(decode_bcd_bytes),
+        Some(value),
+        "{value}"
+      );
+    }
+    assert_eq!(encode_bcd_bytes(1234, 2), Some(vec![0x12, 0x34]));
+  }
+
+  #[test]
+  fn bcd_rejects_too_many_digits() {
+    assert_eq!(encode_bcd_bytes(10_000, 2), None);
+    assert_eq!(decode_bcd_bytes(&[0x1A]), None);
+  }
+
+  #[test]
+  fn schneider_datetime_round_trips() {
+    let bytes = encode_schneider_datetime_bytes(date_time());
+    assert_eq!(decode_schneider_datetime_bytes(&bytes), Some(date_time()));
+  }
+
+  #[test]
+  fn iec870_time_round_trips() {
+    let bytes = encode_iec870_time_bytes(date_time());
+    assert_eq!(decode_iec870_time_bytes(&bytes), Some(date_time()));
+  }
+
+  #[test]
+  fn invalid_clocks_decode_to_none() {
+    let mut bytes = encode_schneider_datetime_bytes(date_time());
+    if let Some(minute) = bytes.get_mut(5) {
+      *minute |= INVALID_FLAG;
+    }
+    assert_eq!(decode_schneider_datetime_bytes(&bytes), None);
+
+    let mut bytes = encode_iec870_time_bytes(date_time());
+    if let Some(minute) = bytes.get_mut(2) {
+      *minute |= INVALID_FLAG;
+    }
+    assert_eq!(decode_iec870_time_bytes(&bytes), None);
+  }
 }
@@ -0,0 +1,149 @@
+use tokio_modbus::{Address, Quantity};
+
+use super::encoding::Order;
+use super::register::*;
+use super::span::Function;
+
+// NOTE: read device identification is the encapsulated interface transport
+// function with the device identification mei type where each object is
+// requested on its own through the specific object access read code
+
+pub(crate) const FUNCTION_CODE: u8 = 0x2B;
+pub(crate) const MEI_TYPE: u8 = 0x0E;
+pub(crate) const SPECIFIC_ACCESS: u8 = 0x04;
+
+// NOTE: regular identification with individual access
+const CONFORMITY_LEVEL: u8 = 0x82;
+
+// NOTE: objects are at most a full pdu long
+pub(crate) const OBJECT_LENGTH: Quantity = 125;
+
+pub(crate) const VENDOR_NAME: Address = 0x00;
+pub(crate) const PRODUCT_CODE: Address = 0x01;
+pub(crate) const REVISION: Address = 0x02;
+
+// NOTE: basic and regular objects
+pub(crate) const OBJECTS: [(Address, &str); 7] = [
+  (VENDOR_NAME, "vendorName"),
+  (PRODUCT_CODE, "productCode"),
+  (REVISION, "majorMinorRevision"),
+  (0x03, "vendorUrl"),
+  (0x04, "productName"),
+  (0x05, "modelName"),
+  (0x06, "userApplicationName"),
+];
+
+pub(crate) fn request(object: Address) -> Vec<u8> {
+  vec![
+    MEI_TYPE,
+    SPECIFIC_ACCESS,
+    u8::try_from(object).unwrap_or(u8::MAX),
+  ]
+}
+
+// NOTE: mei type, read code, conformity level, more follows, next object id,
+// number of objects and then objects as id, length and value
+pub(crate) fn parse_response(
+  object: Address,
+  response: &[u8],
+  quantity: Quantity,
+) -> Option<Vec<u16>> {
+  let (header, mut objects) = response.split_at_checked(6)?;
+  if header.first() != Some(&MEI_TYPE) {
+    return None;
+  }
+
+  let count = header.get(5).copied().unwrap_or(0);
+  for _ in 0..count {
+    let (&[id, length], rest) = objects.split_first_chunk::<2>()?;
+    let (value, rest) = rest.split_at_checked(usize::from(length))?;
+    if Address::from(id) == object {
+      return Some(to_words(value, quantity));
+    }
+    objects = rest;
+  }
+
+  None
+}
+
+pub(crate) fn response(object: Address, value: &[u8]) -> Vec<u8> {
+  let object = u8::try_from(object).unwrap_or(u8::MAX);
+  let value = value
+    .iter()
+    .copied()
+    .take_while(|byte| *byte != 0)
+    .take(usize::from(u8::MAX))
+    .collect::<Vec<_>>();
+  let length = u8::try_from(value.len()).unwrap_or(u8::MAX);
+
+  [
+    MEI_TYPE,
+    SPECIFIC_ACCESS,
+    CONFORMITY_LEVEL,
+    0x00,
+    0x00,
+    0x01,
+    object,
+    length,
+  ]
+  .into_iter()
+  .chain(value)
+  .collect()
+}
+
+pub(crate) fn object_register(
+  object: Address,
+) -> IdRegister<RegisterKindStorage> {
+  IdRegister::<RegisterKindStorage> {
+    address: object,
+    function: Function::DeviceIdentification,
+    storage: RegisterKindStorage::String(StringRegisterKind {
+      length: OBJECT_LENGTH,
+      order: Order::Big,
+    }),
+  }
+}
+
+// NOTE: objects shorter than the span are padded with zeros
+fn to_words(value: &[u8], quantity: Quantity) -> Vec<u16> {
+  let mut words = value
+    .chunks(2)
+    .map(|chunk| match *chunk {
+      [high, low] => u16::from_be_bytes([high, low]),
+      [high] => u16::from_be_bytes([high, 0]),
+      _ => 0,
+    })
+    .take(usize::from(quantity))
+    .collect::<Vec<_>>();
+  words.resize(usize::from(quantity), 0);
+  words
+}
+
+#[cfg(test)]
+mod tests {
+  use either::Either;
+
+  use super::super::span::SpanParser;
+  use super::*;
+
+  #[test]
+  fn padded_objects_match_anchored_patterns() {
+    let response = response(VENDOR_NAME, b"Schneider Electric");
+    #[allow(clippy::unwrap_used, reason = "response has the object")]
+    let words = parse_response(VENDOR_NAME, &response, OBJECT_LENGTH).unwrap();
+    assert_eq!(words.len(), usize::from(OBJECT_LENGTH));
+
+    #[allow(clippy::unwrap_used, reason = "test pattern is valid")]
+    let register = DetectRegister {
+      address: VENDOR_NAME,
+      function: Function::DeviceIdentification,
+      storage: object_register(VENDOR_NAME).storage,
+      r#match: Either::Right(
+        regex::Regex::new("^Schneider Electric$").unwrap(),
+      ),
+    };
+    #[allow(clippy::unwrap_used, reason = "words fill the object length")]
+    let register = register.parse(words).unwrap();
+    assert!(register.matches());
+  }
+}
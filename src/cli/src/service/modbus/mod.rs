@@ -1,11 +1,15 @@
 pub(crate) mod batch;
 pub(crate) mod connection;
 pub(crate) mod encoding;
+pub(crate) mod identification;
 pub(crate) mod record;
 pub(crate) mod register;
+pub(crate) mod server;
 pub(crate) mod service;
 pub(crate) mod span;
+pub(crate) mod sunspec;
 pub(crate) mod time;
+pub(crate) mod udp;
 pub(crate) mod worker;
 
 pub(crate) use connection::Destination;
@@ -1,6 +1,6 @@
 use tokio_modbus::{Address, Quantity};
 
-use super::span::Span;
+use super::span::{Function, Span};
 
 pub(crate) trait Record: Span {
   fn values(&self) -> impl Iterator<Item = u16>;
@@ -20,6 +20,11 @@ impl Span for SimpleRecord {
   fn quantity(&self) -> Quantity {
     self.values.len() as Quantity
   }
+
+  // NOTE: only holding registers are writable
+  fn function(&self) -> Function {
+    Function::HoldingRegisters
+  }
 }
 
 impl Span for &SimpleRecord {
@@ -30,6 +35,11 @@ impl Span for &SimpleRecord {
   fn quantity(&self) -> Quantity {
     self.values.len() as Quantity
   }
+
+  // NOTE: only holding registers are writable
+  fn function(&self) -> Function {
+    Function::HoldingRegisters
+  }
 }
 
 impl Span for Box<SimpleRecord> {
@@ -40,6 +50,11 @@ impl Span for Box<SimpleRecord> {
   fn quantity(&self) -> Quantity {
     self.values.len() as Quantity
   }
+
+  // NOTE: only holding registers are writable
+  fn function(&self) -> Function {
+    Function::HoldingRegisters
+  }
 }
 
 impl Record for SimpleRecord {
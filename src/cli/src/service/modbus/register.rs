@@ -1,7 +1,10 @@
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::iter::IntoIterator;
+use std::sync::Arc;
 
+use chrono::TimeZone;
 use either::Either;
 use regex::Regex;
 use rust_decimal::Decimal;
@@ -20,11 +23,18 @@ pub(crate) trait RegisterStorage {
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct StringRegisterKind {
   pub(crate) length: Quantity,
+  pub(crate) order: Order,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct NumericRegisterKind {
   pub(crate) multiplier: Option<Decimal>,
+  pub(crate) offset: Option<Decimal>,
+  pub(crate) order: Order,
+  pub(crate) scale_register: Option<Address>,
+  // NOTE: raw value devices like sunspec ones report for points they
+  // don't implement which then parses to null
+  pub(crate) not_implemented: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -32,7 +42,30 @@ pub(crate) struct RawRegisterKind {
   pub(crate) length: Quantity,
 }
 
+// NOTE: variants are keyed by their unsigned integer value
+#[derive(Debug, Clone)]
+pub(crate) struct EnumRegisterKind {
+  pub(crate) length: Quantity,
+  pub(crate) order: Order,
+  pub(crate) variants: Arc<BTreeMap<u64, String>>,
+}
+
+// NOTE: flags are keyed by their bit where zero is the least significant bit
+#[derive(Debug, Clone)]
+pub(crate) struct BitfieldRegisterKind {
+  pub(crate) length: Quantity,
+  pub(crate) order: Order,
+  pub(crate) flags: Arc<BTreeMap<u8, String>>,
+}
+
+// NOTE: devices keep their clocks in local time
 #[derive(Debug, Clone, Copy)]
+pub(crate) struct DateTimeRegisterKind {
+  pub(crate) timezone: chrono_tz::Tz,
+  pub(crate) order: Order,
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum RegisterKindStorage {
   U16(NumericRegisterKind),
   U32(NumericRegisterKind),
@@ -42,13 +75,22 @@ pub(crate) enum RegisterKindStorage {
   S64(NumericRegisterKind),
   F32(NumericRegisterKind),
   F64(NumericRegisterKind),
+  U48(NumericRegisterKind),
+  Bcd16(NumericRegisterKind),
+  Bcd32(NumericRegisterKind),
+  Bcd64(NumericRegisterKind),
   String(StringRegisterKind),
   Raw(RawRegisterKind),
+  Enum(EnumRegisterKind),
+  Bitfield(BitfieldRegisterKind),
+  DateTime(DateTimeRegisterKind),
+  Iec870Time(DateTimeRegisterKind),
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct RegisterValue<T> {
   pub(crate) value: T,
+  pub(crate) order: Order,
   pub(crate) timestamp: chrono::DateTime<chrono::Utc>,
 }
 
@@ -62,8 +104,45 @@ pub(crate) enum RegisterValueStorage {
   S64(RegisterValue<Decimal>),
   F32(RegisterValue<Decimal>),
   F64(RegisterValue<Decimal>),
+  U48(RegisterValue<Decimal>),
+  Bcd16(RegisterValue<Decimal>),
+  Bcd32(RegisterValue<Decimal>),
+  Bcd64(RegisterValue<Decimal>),
   String(RegisterValue<String>),
   Raw(RegisterValue<Vec<u16>>),
+  Enum(RegisterValue<EnumValue>),
+  Bitfield(RegisterValue<BitfieldValue>),
+  DateTime(RegisterValue<DateTimeValue>),
+  Iec870Time(RegisterValue<DateTimeValue>),
+  // NOTE: holds the register length of the not implemented value
+  Null(RegisterValue<Quantity>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EnumValue {
+  pub(crate) value: u64,
+  pub(crate) length: Quantity,
+  pub(crate) name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BitfieldValue {
+  pub(crate) value: u64,
+  pub(crate) length: Quantity,
+  pub(crate) flags: Vec<(String, bool)>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DateTimeValue {
+  // NOTE: none when the device flags its clock as invalid
+  pub(crate) value: Option<chrono::DateTime<chrono::Utc>>,
+  pub(crate) timezone: chrono_tz::Tz,
+}
+
+impl DateTimeValue {
+  fn local(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+    self.value.map(|value| value.with_timezone(&self.timezone))
+  }
 }
 
 impl RegisterValueStorage {
@@ -77,8 +156,78 @@ impl RegisterValueStorage {
       RegisterValueStorage::S64(storage) => storage.timestamp,
       RegisterValueStorage::F32(storage) => storage.timestamp,
       RegisterValueStorage::F64(storage) => storage.timestamp,
+      RegisterValueStorage::U48(storage) => storage.timestamp,
+      RegisterValueStorage::Bcd16(storage) => storage.timestamp,
+      RegisterValueStorage::Bcd32(storage) => storage.timestamp,
+      RegisterValueStorage::Bcd64(storage) => storage.timestamp,
       RegisterValueStorage::String(storage) => storage.timestamp,
       RegisterValueStorage::Raw(storage) => storage.timestamp,
+      RegisterValueStorage::Enum(storage) => storage.timestamp,
+      RegisterValueStorage::Bitfield(storage) => storage.timestamp,
+      RegisterValueStorage::DateTime(storage) => storage.timestamp,
+      RegisterValueStorage::Iec870Time(storage) => storage.timestamp,
+      RegisterValueStorage::Null(storage) => storage.timestamp,
+    }
+  }
+
+  pub(crate) fn number(&self) -> Option<Decimal> {
+    match self {
+      RegisterValueStorage::U16(storage)
+      | RegisterValueStorage::U32(storage)
+      | RegisterValueStorage::U64(storage)
+      | RegisterValueStorage::S16(storage)
+      | RegisterValueStorage::S32(storage)
+      | RegisterValueStorage::S64(storage)
+      | RegisterValueStorage::F32(storage)
+      | RegisterValueStorage::F64(storage)
+      | RegisterValueStorage::U48(storage)
+      | RegisterValueStorage::Bcd16(storage)
+      | RegisterValueStorage::Bcd32(storage)
+      | RegisterValueStorage::Bcd64(storage) => Some(storage.value),
+      RegisterValueStorage::Enum(storage) => Some(storage.value.value.into()),
+      RegisterValueStorage::Bitfield(storage) => {
+        Some(storage.value.value.into())
+      }
+      RegisterValueStorage::String(_)
+      | RegisterValueStorage::Raw(_)
+      | RegisterValueStorage::DateTime(_)
+      | RegisterValueStorage::Iec870Time(_)
+      | RegisterValueStorage::Null(_) => None,
+    }
+  }
+
+  // NOTE: offsets are added after scaling
+  fn scale(&mut self, scale: Decimal, offset: Option<Decimal>) {
+    match self {
+      RegisterValueStorage::U16(storage)
+      | RegisterValueStorage::U32(storage)
+      | RegisterValueStorage::U64(storage)
+      | RegisterValueStorage::S16(storage)
+      | RegisterValueStorage::S32(storage)
+      | RegisterValueStorage::S64(storage)
+      | RegisterValueStorage::F32(storage)
+      | RegisterValueStorage::F64(storage)
+      | RegisterValueStorage::U48(storage)
+      | RegisterValueStorage::Bcd16(storage)
+      | RegisterValueStorage::Bcd32(storage)
+      | RegisterValueStorage::Bcd64(storage) => {
+        let offset = offset.unwrap_or_default();
+        let value = storage
+          .value
+          .checked_sub(offset)
+          .and_then(|value| value.checked_mul(scale))
+          .and_then(|value| value.checked_add(offset));
+        if let Some(value) = value {
+          storage.value = value.normalize();
+        }
+      }
+      RegisterValueStorage::String(_)
+      | RegisterValueStorage::Raw(_)
+      | RegisterValueStorage::Enum(_)
+      | RegisterValueStorage::Bitfield(_)
+      | RegisterValueStorage::DateTime(_)
+      | RegisterValueStorage::Iec870Time(_)
+      | RegisterValueStorage::Null(_) => {}
     }
   }
 
@@ -92,6 +241,10 @@ impl RegisterValueStorage {
       RegisterValueStorage::S64(storage) => serde_json::json!(storage.value),
       RegisterValueStorage::F32(storage) => serde_json::json!(storage.value),
       RegisterValueStorage::F64(storage) => serde_json::json!(storage.value),
+      RegisterValueStorage::U48(storage) => serde_json::json!(storage.value),
+      RegisterValueStorage::Bcd16(storage) => serde_json::json!(storage.value),
+      RegisterValueStorage::Bcd32(storage) => serde_json::json!(storage.value),
+      RegisterValueStorage::Bcd64(storage) => serde_json::json!(storage.value),
       RegisterValueStorage::String(storage) => serde_json::json!(storage.value),
       RegisterValueStorage::Raw(storage) => {
         serde_json::json!(storage
@@ -100,6 +253,23 @@ impl RegisterValueStorage {
           .map(|&num| format!("0x{:04X}", num))
           .collect::<Vec<_>>())
       }
+      RegisterValueStorage::Enum(storage) => match &storage.value.name {
+        Some(name) => serde_json::json!(name),
+        None => serde_json::json!(storage.value.value),
+      },
+      RegisterValueStorage::Bitfield(storage) => serde_json::Value::Object(
+        storage
+          .value
+          .flags
+          .iter()
+          .map(|(name, set)| (name.clone(), serde_json::json!(set)))
+          .collect(),
+      ),
+      RegisterValueStorage::DateTime(storage)
+      | RegisterValueStorage::Iec870Time(storage) => {
+        serde_json::json!(storage.value.local().map(|value| value.to_rfc3339()))
+      }
+      RegisterValueStorage::Null(_) => serde_json::Value::Null,
     }
   }
 }
@@ -107,6 +277,7 @@ impl RegisterValueStorage {
 #[derive(Debug, Clone)]
 pub(crate) struct MeasurementRegister<T: RegisterStorage> {
   pub(crate) address: Address,
+  pub(crate) function: Function,
   pub(crate) storage: T,
   pub(crate) name: String,
 }
@@ -114,6 +285,7 @@ pub(crate) struct MeasurementRegister<T: RegisterStorage> {
 #[derive(Debug, Clone)]
 pub(crate) struct DetectRegister<T: RegisterStorage> {
   pub(crate) address: Address,
+  pub(crate) function: Function,
   pub(crate) storage: T,
   pub(crate) r#match: Either<String, Regex>,
 }
@@ -121,6 +293,7 @@ pub(crate) struct DetectRegister<T: RegisterStorage> {
 #[derive(Debug, Clone)]
 pub(crate) struct IdRegister<T: RegisterStorage> {
   pub(crate) address: Address,
+  pub(crate) function: Function,
   pub(crate) storage: T,
 }
 
@@ -153,10 +326,374 @@ impl RegisterStorage for RegisterKindStorage {
       RegisterKindStorage::S64(_) => 4,
       RegisterKindStorage::F32(_) => 2,
       RegisterKindStorage::F64(_) => 4,
-      RegisterKindStorage::String(StringRegisterKind { length }) => *length,
+      RegisterKindStorage::U48(_) => 3,
+      RegisterKindStorage::Bcd16(_) => 1,
+      RegisterKindStorage::Bcd32(_) => 2,
+      RegisterKindStorage::Bcd64(_) => 4,
+      RegisterKindStorage::String(StringRegisterKind { length, .. }) => *length,
       RegisterKindStorage::Raw(RawRegisterKind { length }) => *length,
+      RegisterKindStorage::Enum(EnumRegisterKind { length, .. }) => *length,
+      RegisterKindStorage::Bitfield(BitfieldRegisterKind {
+        length, ..
+      }) => *length,
+      RegisterKindStorage::DateTime(_) => 4,
+      RegisterKindStorage::Iec870Time(_) => 4,
+    }
+  }
+}
+
+impl RegisterKindStorage {
+  pub(crate) fn scale_register(&self) -> Option<Address> {
+    match self {
+      RegisterKindStorage::U16(kind)
+      | RegisterKindStorage::U32(kind)
+      | RegisterKindStorage::U64(kind)
+      | RegisterKindStorage::S16(kind)
+      | RegisterKindStorage::S32(kind)
+      | RegisterKindStorage::S64(kind)
+      | RegisterKindStorage::F32(kind)
+      | RegisterKindStorage::F64(kind)
+      | RegisterKindStorage::U48(kind)
+      | RegisterKindStorage::Bcd16(kind)
+      | RegisterKindStorage::Bcd32(kind)
+      | RegisterKindStorage::Bcd64(kind) => kind.scale_register,
+      _ => None,
+    }
+  }
+
+  pub(crate) fn offset(&self) -> Option<Decimal> {
+    match self {
+      RegisterKindStorage::U16(kind)
+      | RegisterKindStorage::U32(kind)
+      | RegisterKindStorage::U64(kind)
+      | RegisterKindStorage::S16(kind)
+      | RegisterKindStorage::S32(kind)
+      | RegisterKindStorage::S64(kind)
+      | RegisterKindStorage::F32(kind)
+      | RegisterKindStorage::F64(kind)
+      | RegisterKindStorage::U48(kind)
+      | RegisterKindStorage::Bcd16(kind)
+      | RegisterKindStorage::Bcd32(kind)
+      | RegisterKindStorage::Bcd64(kind) => kind.offset,
+      _ => None,
+    }
+  }
+
+  pub(crate) fn not_implemented(&self) -> Option<(u64, Order)> {
+    match self {
+      RegisterKindStorage::U16(kind)
+      | RegisterKindStorage::U32(kind)
+      | RegisterKindStorage::U64(kind)
+      | RegisterKindStorage::S16(kind)
+      | RegisterKindStorage::S32(kind)
+      | RegisterKindStorage::S64(kind)
+      | RegisterKindStorage::F32(kind)
+      | RegisterKindStorage::F64(kind)
+      | RegisterKindStorage::U48(kind)
+      | RegisterKindStorage::Bcd16(kind)
+      | RegisterKindStorage::Bcd32(kind)
+      | RegisterKindStorage::Bcd64(kind) => {
+        Some((kind.not_implemented?, kind.order))
+      }
+      _ => None,
+    }
+  }
+}
+
+// NOTE: the inverse of parsing so multipliers get divided back out
+// and strings and raw values get padded to the register length
+impl RegisterKindStorage {
+  pub(crate) fn encode_number(&self, number: Decimal) -> Vec<u16> {
+    let storage = match self.clone() {
+      RegisterKindStorage::U16(kind) => {
+        RegisterValueStorage::U16(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::U32(kind) => {
+        RegisterValueStorage::U32(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::U64(kind) => {
+        RegisterValueStorage::U64(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::S16(kind) => {
+        RegisterValueStorage::S16(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::S32(kind) => {
+        RegisterValueStorage::S32(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::S64(kind) => {
+        RegisterValueStorage::S64(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::F32(kind) => {
+        RegisterValueStorage::F32(to_raw_value(number, kind, false))
+      }
+      RegisterKindStorage::F64(kind) => {
+        RegisterValueStorage::F64(to_raw_value(number, kind, false))
+      }
+      RegisterKindStorage::U48(kind) => {
+        RegisterValueStorage::U48(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::Bcd16(kind) => {
+        RegisterValueStorage::Bcd16(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::Bcd32(kind) => {
+        RegisterValueStorage::Bcd32(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::Bcd64(kind) => {
+        RegisterValueStorage::Bcd64(to_raw_value(number, kind, true))
+      }
+      RegisterKindStorage::DateTime(_) | RegisterKindStorage::Iec870Time(_) => {
+        let seconds = i64::try_from(number.trunc()).unwrap_or_default();
+        return self
+          .encode_date_time(chrono::DateTime::from_timestamp(seconds, 0));
+      }
+      RegisterKindStorage::String(_) => {
+        return self.encode_text(number.to_string().as_str());
+      }
+      RegisterKindStorage::Raw(_) => {
+        let bytes = u64::try_from(number.trunc())
+          .unwrap_or_default()
+          .to_be_bytes();
+        return self.encode_raw(encode_numeric_bytes(bytes, Order::Big));
+      }
+      RegisterKindStorage::Enum(EnumRegisterKind { length, order, .. })
+      | RegisterKindStorage::Bitfield(BitfieldRegisterKind {
+        length,
+        order,
+        ..
+      }) => {
+        let value = u64::try_from(number.trunc()).unwrap_or_default();
+        return encode_unsigned(value, length, order);
+      }
+    };
+
+    ValueRegister::<RegisterValueStorage> {
+      address: 0,
+      storage,
+    }
+    .values()
+    .collect()
+  }
+
+  pub(crate) fn encode_text(&self, text: &str) -> Vec<u16> {
+    match self {
+      RegisterKindStorage::String(StringRegisterKind { length, order }) => {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(usize::from(*length).saturating_mul(2), 0);
+        encode_string_bytes(bytes, *order)
+      }
+      RegisterKindStorage::Enum(EnumRegisterKind {
+        length,
+        order,
+        variants,
+      }) => match variants.iter().find(|(_, name)| name.as_str() == text) {
+        Some((value, _)) => encode_unsigned(*value, *length, *order),
+        None => self.encode_number(text.trim().parse().unwrap_or_default()),
+      },
+      // NOTE: anything but an rfc3339 date time encodes the current time
+      // so simulated clocks keep ticking
+      RegisterKindStorage::DateTime(_) | RegisterKindStorage::Iec870Time(_) => {
+        self.encode_date_time(Some(
+          chrono::DateTime::parse_from_rfc3339(text.trim())
+            .map_or_else(|_| chrono::Utc::now(), |value| value.to_utc()),
+        ))
+      }
+      _ => self.encode_number(text.trim().parse().unwrap_or_default()),
     }
   }
+
+  pub(crate) fn encode_value(&self, value: &RegisterValueStorage) -> Vec<u16> {
+    match value {
+      RegisterValueStorage::U16(storage)
+      | RegisterValueStorage::U32(storage)
+      | RegisterValueStorage::U64(storage)
+      | RegisterValueStorage::S16(storage)
+      | RegisterValueStorage::S32(storage)
+      | RegisterValueStorage::S64(storage)
+      | RegisterValueStorage::F32(storage)
+      | RegisterValueStorage::F64(storage)
+      | RegisterValueStorage::U48(storage)
+      | RegisterValueStorage::Bcd16(storage)
+      | RegisterValueStorage::Bcd32(storage)
+      | RegisterValueStorage::Bcd64(storage) => {
+        self.encode_number(storage.value)
+      }
+      RegisterValueStorage::String(storage) => {
+        self.encode_text(storage.value.trim_end_matches('\0'))
+      }
+      RegisterValueStorage::Raw(storage) => {
+        self.encode_raw(storage.value.clone())
+      }
+      RegisterValueStorage::Enum(storage) => {
+        self.encode_number(storage.value.value.into())
+      }
+      RegisterValueStorage::Bitfield(storage) => {
+        self.encode_number(storage.value.value.into())
+      }
+      RegisterValueStorage::DateTime(storage)
+      | RegisterValueStorage::Iec870Time(storage) => {
+        self.encode_date_time(storage.value.value)
+      }
+      RegisterValueStorage::Null(_) => match self.not_implemented() {
+        Some((value, order)) => encode_unsigned(value, self.quantity(), order),
+        None => self.encode_raw(Vec::new()),
+      },
+    }
+  }
+
+  fn encode_date_time(
+    &self,
+    value: Option<chrono::DateTime<chrono::Utc>>,
+  ) -> Vec<u16> {
+    match *self {
+      RegisterKindStorage::DateTime(DateTimeRegisterKind {
+        timezone,
+        order,
+      }) => encode_schneider_datetime(value, timezone, order),
+      RegisterKindStorage::Iec870Time(DateTimeRegisterKind {
+        timezone,
+        order,
+      }) => encode_iec870_time(value, timezone, order),
+      _ => self.encode_raw(Vec::new()),
+    }
+  }
+
+  fn encode_raw(&self, words: Vec<u16>) -> Vec<u16> {
+    let length = usize::from(self.quantity());
+    let padding = length.saturating_sub(words.len());
+    let skip = words.len().saturating_sub(length);
+    std::iter::repeat_n(0u16, padding)
+      .chain(words.into_iter().skip(skip))
+      .collect()
+  }
+}
+
+fn to_raw_value(
+  number: Decimal,
+  kind: NumericRegisterKind,
+  integer: bool,
+) -> RegisterValue<Decimal> {
+  let number = match kind.offset {
+    Some(offset) => number.checked_sub(offset).unwrap_or_default(),
+    None => number,
+  };
+  let value = match kind.multiplier {
+    Some(multiplier) if !multiplier.is_zero() => {
+      number.checked_div(multiplier).unwrap_or_default()
+    }
+    _ => number,
+  };
+
+  RegisterValue::<Decimal> {
+    value: if integer { value.round() } else { value },
+    order: kind.order,
+    timestamp: chrono::Utc::now(),
+  }
+}
+
+const U48_MAX: u64 = 0xFFFF_FFFF_FFFF;
+
+// NOTE: enums and bitfields span up to four registers
+// so they always fit an unsigned 64 bit integer
+fn encode_unsigned(value: u64, length: Quantity, order: Order) -> Vec<u16> {
+  let bytes = value.to_be_bytes();
+  let width = usize::from(length).saturating_mul(2);
+  let padding = width.saturating_sub(bytes.len());
+  let skip = bytes.len().saturating_sub(width);
+  encode_numeric_bytes(
+    std::iter::repeat_n(0u8, padding).chain(bytes.into_iter().skip(skip)),
+    order,
+  )
+}
+
+fn encode_bcd(value: Decimal, length: Quantity, order: Order) -> Vec<u16> {
+  let value = u64::try_from(value).unwrap_or_default();
+  let width = usize::from(length).saturating_mul(2);
+  let bytes = encode_bcd_bytes(value, width).unwrap_or_else(|| {
+    tracing::warn!("Value {} has too many digits for bcd", value);
+    vec![0u8; width]
+  });
+  encode_numeric_bytes(bytes, order)
+}
+
+// NOTE: invalid clocks are all zeros which no device reads as a valid date
+fn encode_schneider_datetime(
+  value: Option<chrono::DateTime<chrono::Utc>>,
+  timezone: chrono_tz::Tz,
+  order: Order,
+) -> Vec<u16> {
+  match value {
+    Some(value) => encode_numeric_bytes(
+      encode_schneider_datetime_bytes(
+        value.with_timezone(&timezone).naive_local(),
+      ),
+      order,
+    ),
+    None => vec![0; 4],
+  }
+}
+
+fn encode_iec870_time(
+  value: Option<chrono::DateTime<chrono::Utc>>,
+  timezone: chrono_tz::Tz,
+  order: Order,
+) -> Vec<u16> {
+  match value {
+    Some(value) => encode_numeric_bytes(
+      encode_iec870_time_bytes(value.with_timezone(&timezone).naive_local()),
+      order,
+    ),
+    None => vec![0; 4],
+  }
+}
+
+fn to_date_time(
+  value: Option<chrono::NaiveDateTime>,
+  timezone: chrono_tz::Tz,
+) -> DateTimeValue {
+  DateTimeValue {
+    value: value
+      .and_then(|value| timezone.from_local_datetime(&value).earliest())
+      .map(|value| value.to_utc()),
+    timezone,
+  }
+}
+
+fn to_measured_value(
+  value: Decimal,
+  multiplier: Option<Decimal>,
+  offset: Option<Decimal>,
+) -> anyhow::Result<Decimal> {
+  let value = match multiplier {
+    Some(multiplier) => value
+      .checked_mul(multiplier)
+      .ok_or_else(|| anyhow::anyhow!("Failed multiplying register"))?,
+    None => value,
+  };
+  Ok(match offset {
+    Some(offset) => value
+      .checked_add(offset)
+      .ok_or_else(|| anyhow::anyhow!("Failed offsetting register"))?,
+    None => value,
+  })
+}
+
+fn decode_unsigned<TIterator, TIntoIterator>(
+  data: TIntoIterator,
+  order: Order,
+) -> anyhow::Result<u64>
+where
+  TIterator: DoubleEndedIterator<Item = u16>,
+  TIntoIterator: IntoIterator<Item = u16, IntoIter = TIterator>,
+{
+  let bytes = decode_numeric_bytes(data, order);
+  let mut buffer = [0u8; 8];
+  let skip = buffer.len().saturating_sub(bytes.len());
+  buffer
+    .get_mut(skip..)
+    .filter(|tail| tail.len() == bytes.len())
+    .ok_or_else(|| anyhow::anyhow!("Unsigned register is too long"))?
+    .copy_from_slice(bytes.as_slice());
+  Ok(u64::from_be_bytes(buffer))
 }
 
 impl RegisterStorage for RegisterValueStorage {
@@ -170,8 +707,17 @@ impl RegisterStorage for RegisterValueStorage {
       RegisterValueStorage::S64(_) => 4,
       RegisterValueStorage::F32(_) => 2,
       RegisterValueStorage::F64(_) => 4,
+      RegisterValueStorage::U48(_) => 3,
+      RegisterValueStorage::Bcd16(_) => 1,
+      RegisterValueStorage::Bcd32(_) => 2,
+      RegisterValueStorage::Bcd64(_) => 4,
       RegisterValueStorage::String(storage) => storage.value.len() as Quantity,
       RegisterValueStorage::Raw(storage) => storage.value.len() as Quantity,
+      RegisterValueStorage::Enum(storage) => storage.value.length,
+      RegisterValueStorage::Bitfield(storage) => storage.value.length,
+      RegisterValueStorage::DateTime(_) => 4,
+      RegisterValueStorage::Iec870Time(_) => 4,
+      RegisterValueStorage::Null(storage) => storage.value,
     }
   }
 }
@@ -203,7 +749,11 @@ impl Display for RegisterValueStorage {
       RegisterValueStorage::F32(storage) => {
         std::fmt::Display::fmt(&storage.value, f)
       }
-      RegisterValueStorage::F64(storage) => {
+      RegisterValueStorage::F64(storage)
+      | RegisterValueStorage::U48(storage)
+      | RegisterValueStorage::Bcd16(storage)
+      | RegisterValueStorage::Bcd32(storage)
+      | RegisterValueStorage::Bcd64(storage) => {
         std::fmt::Display::fmt(&storage.value, f)
       }
       RegisterValueStorage::String(storage) => {
@@ -212,6 +762,21 @@ impl Display for RegisterValueStorage {
       RegisterValueStorage::Raw(storage) => {
         std::fmt::Debug::fmt(&storage.value.iter().map(|&num| Hex(num)), f)
       }
+      RegisterValueStorage::Enum(storage) => match &storage.value.name {
+        Some(name) => std::fmt::Display::fmt(name, f),
+        None => std::fmt::Display::fmt(&storage.value.value, f),
+      },
+      RegisterValueStorage::Bitfield(storage) => {
+        std::fmt::Display::fmt(&storage.value.value, f)
+      }
+      RegisterValueStorage::DateTime(storage)
+      | RegisterValueStorage::Iec870Time(storage) => {
+        match storage.value.local() {
+          Some(value) => std::fmt::Display::fmt(&value.to_rfc3339(), f),
+          None => write!(f, "invalid"),
+        }
+      }
+      RegisterValueStorage::Null(_) => write!(f, "null"),
     }
   }
 }
@@ -225,8 +790,15 @@ impl std::fmt::Debug for Hex {
 }
 
 impl DetectRegister<RegisterValueStorage> {
+  // NOTE: strings match their decoded text without the zero padding
+  // rather than the quoted display
   pub(crate) fn matches(&self) -> bool {
-    let storage = self.storage.to_string();
+    let storage = match &self.storage {
+      RegisterValueStorage::String(storage) => {
+        storage.value.trim_end_matches('\0').to_string()
+      }
+      storage => storage.to_string(),
+    };
     match &self.r#match {
       Either::Left(string) => string.eq(storage.as_str()),
       Either::Right(regex) => regex.is_match(storage.as_str()),
@@ -251,6 +823,80 @@ pub(crate) fn serialize_registers<
   )
 }
 
+// NOTE: scale registers hold signed powers of ten like in sunspec
+// and their not implemented value leaves registers unscaled
+const SCALE_NOT_IMPLEMENTED: i64 = -32768;
+
+pub(crate) fn apply_scale_registers(
+  kinds: &[MeasurementRegister<RegisterKindStorage>],
+  registers: &mut [MeasurementRegister<RegisterValueStorage>],
+) {
+  let scales = kinds
+    .iter()
+    .filter_map(|kind| {
+      let scale_register = kind.storage.scale_register()?;
+      let exponent = registers
+        .iter()
+        .find(|register| {
+          register.function == kind.function
+            && register.address == scale_register
+        })?
+        .storage
+        .number()?;
+      let exponent = i64::try_from(exponent.trunc()).ok()?;
+      if exponent == SCALE_NOT_IMPLEMENTED {
+        return None;
+      }
+      Some((
+        kind.name.as_str(),
+        power_of_ten(exponent)?,
+        kind.storage.offset(),
+      ))
+    })
+    .collect::<Vec<_>>();
+
+  for (name, scale, offset) in scales {
+    for register in registers
+      .iter_mut()
+      .filter(|register| register.name == name)
+    {
+      register.storage.scale(scale, offset);
+    }
+  }
+}
+
+// NOTE: scale registers can be configured apart from the registers they
+// scale so they have to be read alongside them for scaling to work
+pub(crate) fn referenced_scale_registers<'a>(
+  kinds: &[MeasurementRegister<RegisterKindStorage>],
+  candidates: impl IntoIterator<Item = &'a MeasurementRegister<RegisterKindStorage>>,
+) -> Vec<MeasurementRegister<RegisterKindStorage>> {
+  let mut referenced = Vec::<MeasurementRegister<RegisterKindStorage>>::new();
+  for candidate in candidates {
+    let is_referenced = kinds.iter().any(|kind| {
+      kind.function == candidate.function
+        && kind.storage.scale_register() == Some(candidate.address)
+    });
+    let is_known = kinds
+      .iter()
+      .chain(referenced.iter())
+      .any(|kind| kind.name == candidate.name);
+    if is_referenced && !is_known {
+      referenced.push(candidate.clone());
+    }
+  }
+
+  referenced
+}
+
+fn power_of_ten(exponent: i64) -> Option<Decimal> {
+  let scale = u32::try_from(exponent.unsigned_abs()).ok()?;
+  if exponent < 0 {
+    return Decimal::try_new(1, scale).ok();
+  }
+  (0..scale).try_fold(Decimal::ONE, |power, _| power.checked_mul(Decimal::TEN))
+}
+
 macro_rules! impl_display {
   ($type: ident) => {
     impl Display for $type<RegisterValueStorage> {
@@ -271,6 +917,9 @@ impl_display!(ValueRegister);
 
 macro_rules! impl_span {
   ($type: ident) => {
+    impl_span!($type, |_register| Function::HoldingRegisters);
+  };
+  ($type: ident, $function: expr) => {
     impl<T: RegisterStorage> Span for $type<T> {
       fn address(&self) -> Address {
         self.address
@@ -279,6 +928,11 @@ macro_rules! impl_span {
       fn quantity(&self) -> Quantity {
         self.storage.quantity()
       }
+
+      fn function(&self) -> Function {
+        #[allow(clippy::redundant_closure_call, reason = "easier for macro")]
+        $function(self)
+      }
     }
 
     impl<T: RegisterStorage> Span for &$type<T> {
@@ -289,6 +943,11 @@ macro_rules! impl_span {
       fn quantity(&self) -> Quantity {
         self.storage.quantity()
       }
+
+      fn function(&self) -> Function {
+        #[allow(clippy::redundant_closure_call, reason = "easier for macro")]
+        $function(*self)
+      }
     }
 
     impl<T: RegisterStorage> Span for Box<$type<T>> {
@@ -299,49 +958,71 @@ macro_rules! impl_span {
       fn quantity(&self) -> Quantity {
         self.storage.quantity()
       }
+
+      fn function(&self) -> Function {
+        #[allow(clippy::redundant_closure_call, reason = "easier for macro")]
+        $function(self.as_ref())
+      }
     }
   };
 }
 
-impl_span!(MeasurementRegister);
-impl_span!(DetectRegister);
-impl_span!(IdRegister);
+impl_span!(MeasurementRegister, |register: &MeasurementRegister<T>| {
+  register.function
+});
+impl_span!(DetectRegister, |register: &DetectRegister<T>| register
+  .function);
+impl_span!(IdRegister, |register: &IdRegister<T>| register.function);
 impl_span!(ValueRegister);
 
 macro_rules! parse_integer_register {
-  ($variant: ident, $type: ty, $data: ident, $multiplier: ident, $timestamp: expr) => {{
-    let bytes = decode_numeric_bytes($data);
+  ($variant: ident, $type: ty, $data: ident, $multiplier: ident, $offset: ident, $order: ident, $timestamp: expr) => {{
+    let bytes = decode_numeric_bytes($data, $order);
     let slice = bytes.as_slice().try_into()?;
-    let mut typed = <$type>::from_ne_bytes(slice);
+    let mut typed = <$type>::from_be_bytes(slice);
     if (typed == <$type>::MAX) {
       typed = 0;
     }
 
     let value = Decimal::from(typed);
+    let value = match $multiplier {
+      Some($multiplier) => value
+        .checked_mul($multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Failed multiplying register"))?,
+      None => value,
+    };
     RegisterValueStorage::$variant(RegisterValue::<Decimal> {
-      value: match $multiplier {
-        Some($multiplier) => value
-          .checked_mul($multiplier)
-          .ok_or_else(|| anyhow::anyhow!("Failed multiplying register"))?,
+      value: match $offset {
+        Some($offset) => value
+          .checked_add($offset)
+          .ok_or_else(|| anyhow::anyhow!("Failed offsetting register"))?,
         None => value,
       },
+      order: $order,
       timestamp: $timestamp,
     })
   }};
 }
 
 macro_rules! parse_floating_register {
-  ($variant: ident, $type: ty, $data: ident, $multiplier: ident, $timestamp: expr) => {{
-    let bytes = decode_numeric_bytes($data);
+  ($variant: ident, $type: ty, $data: ident, $multiplier: ident, $offset: ident, $order: ident, $timestamp: expr) => {{
+    let bytes = decode_numeric_bytes($data, $order);
     let slice = bytes.as_slice().try_into()?;
-    let value = Decimal::try_from(<$type>::from_ne_bytes(slice))?;
+    let value = Decimal::try_from(<$type>::from_be_bytes(slice))?;
+    let value = match $multiplier {
+      Some($multiplier) => value
+        .checked_mul($multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Failed multiplying register"))?,
+      None => value,
+    };
     RegisterValueStorage::$variant(RegisterValue::<Decimal> {
-      value: match $multiplier {
-        Some($multiplier) => value
-          .checked_mul($multiplier)
-          .ok_or_else(|| anyhow::anyhow!("Failed multiplying register"))?,
+      value: match $offset {
+        Some($offset) => value
+          .checked_add($offset)
+          .ok_or_else(|| anyhow::anyhow!("Failed offsetting register"))?,
         None => value,
       },
+      order: $order,
       timestamp: $timestamp,
     })
   }};
@@ -349,45 +1030,249 @@ macro_rules! parse_floating_register {
 
 macro_rules! parse_register {
   ($self: ident, $data: ident, $result: expr, $timestamp: expr) => {{
+    let $data = $data.into_iter().collect::<Vec<_>>();
+    let not_implemented =
+      $self
+        .storage
+        .not_implemented()
+        .filter(|(not_implemented, order)| {
+          decode_unsigned($data.iter().copied(), *order).ok()
+            == Some(*not_implemented)
+        });
     let value = match $self.storage {
-      RegisterKindStorage::U16(NumericRegisterKind { multiplier }) => {
-        parse_integer_register!(U16, u16, $data, multiplier, $timestamp)
+      RegisterKindStorage::U16(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_integer_register!(
+          U16, u16, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::U32(NumericRegisterKind { multiplier }) => {
-        parse_integer_register!(U32, u32, $data, multiplier, $timestamp)
+      RegisterKindStorage::U32(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_integer_register!(
+          U32, u32, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::U64(NumericRegisterKind { multiplier }) => {
-        parse_integer_register!(U64, u64, $data, multiplier, $timestamp)
+      RegisterKindStorage::U64(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_integer_register!(
+          U64, u64, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::S16(NumericRegisterKind { multiplier }) => {
-        parse_integer_register!(S16, i16, $data, multiplier, $timestamp)
+      RegisterKindStorage::S16(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_integer_register!(
+          S16, i16, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::S32(NumericRegisterKind { multiplier }) => {
-        parse_integer_register!(S32, i32, $data, multiplier, $timestamp)
+      RegisterKindStorage::S32(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_integer_register!(
+          S32, i32, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::S64(NumericRegisterKind { multiplier }) => {
-        parse_integer_register!(S64, i64, $data, multiplier, $timestamp)
+      RegisterKindStorage::S64(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_integer_register!(
+          S64, i64, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::F32(NumericRegisterKind { multiplier }) => {
-        parse_floating_register!(F32, f32, $data, multiplier, $timestamp)
+      RegisterKindStorage::F32(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_floating_register!(
+          F32, f32, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::F64(NumericRegisterKind { multiplier }) => {
-        parse_floating_register!(F64, f64, $data, multiplier, $timestamp)
+      RegisterKindStorage::F64(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        parse_floating_register!(
+          F64, f64, $data, multiplier, offset, order, $timestamp
+        )
       }
-      RegisterKindStorage::String(_) => {
-        let bytes = decode_string_bytes($data);
+      RegisterKindStorage::U48(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        let mut value = decode_unsigned($data, order)?;
+        if value == U48_MAX {
+          value = 0;
+        }
+        RegisterValueStorage::U48(RegisterValue::<Decimal> {
+          value: to_measured_value(Decimal::from(value), multiplier, offset)?,
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::Bcd16(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        let bytes = decode_numeric_bytes($data, order);
+        let value = decode_bcd_bytes(bytes.as_slice())
+          .ok_or_else(|| anyhow::anyhow!("Invalid bcd register"))?;
+        RegisterValueStorage::Bcd16(RegisterValue::<Decimal> {
+          value: to_measured_value(Decimal::from(value), multiplier, offset)?,
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::Bcd32(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        let bytes = decode_numeric_bytes($data, order);
+        let value = decode_bcd_bytes(bytes.as_slice())
+          .ok_or_else(|| anyhow::anyhow!("Invalid bcd register"))?;
+        RegisterValueStorage::Bcd32(RegisterValue::<Decimal> {
+          value: to_measured_value(Decimal::from(value), multiplier, offset)?,
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::Bcd64(NumericRegisterKind {
+        multiplier,
+        offset,
+        order,
+        ..
+      }) => {
+        let bytes = decode_numeric_bytes($data, order);
+        let value = decode_bcd_bytes(bytes.as_slice())
+          .ok_or_else(|| anyhow::anyhow!("Invalid bcd register"))?;
+        RegisterValueStorage::Bcd64(RegisterValue::<Decimal> {
+          value: to_measured_value(Decimal::from(value), multiplier, offset)?,
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::DateTime(DateTimeRegisterKind {
+        timezone,
+        order,
+      }) => {
+        let bytes = decode_numeric_bytes($data, order);
+        RegisterValueStorage::DateTime(RegisterValue::<DateTimeValue> {
+          value: to_date_time(
+            decode_schneider_datetime_bytes(bytes.as_slice()),
+            timezone,
+          ),
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::Iec870Time(DateTimeRegisterKind {
+        timezone,
+        order,
+      }) => {
+        let bytes = decode_numeric_bytes($data, order);
+        RegisterValueStorage::Iec870Time(RegisterValue::<DateTimeValue> {
+          value: to_date_time(
+            decode_iec870_time_bytes(bytes.as_slice()),
+            timezone,
+          ),
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::String(StringRegisterKind { order, .. }) => {
+        let bytes = decode_string_bytes($data, order);
         RegisterValueStorage::String(RegisterValue::<String> {
           value: String::from_utf8(bytes)?,
+          order,
           timestamp: $timestamp,
         })
       }
       RegisterKindStorage::Raw(_) => {
         RegisterValueStorage::Raw(RegisterValue::<Vec<u16>> {
           value: $data.into_iter().collect::<Vec<_>>(),
+          order: Order::Big,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::Enum(EnumRegisterKind {
+        length,
+        order,
+        ref variants,
+      }) => {
+        let value = decode_unsigned($data, order)?;
+        RegisterValueStorage::Enum(RegisterValue::<EnumValue> {
+          value: EnumValue {
+            value,
+            length,
+            name: variants.get(&value).cloned(),
+          },
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      RegisterKindStorage::Bitfield(BitfieldRegisterKind {
+        length,
+        order,
+        ref flags,
+      }) => {
+        let value = decode_unsigned($data, order)?;
+        RegisterValueStorage::Bitfield(RegisterValue::<BitfieldValue> {
+          value: BitfieldValue {
+            value,
+            length,
+            flags: flags
+              .iter()
+              .map(|(bit, name)| {
+                let set = value.checked_shr(u32::from(*bit)).unwrap_or(0) & 1;
+                (name.clone(), set == 1)
+              })
+              .collect(),
+          },
+          order,
           timestamp: $timestamp,
         })
       }
     };
+    let value = match not_implemented {
+      Some((_, order)) => {
+        RegisterValueStorage::Null(RegisterValue::<Quantity> {
+          value: $self.storage.quantity(),
+          order,
+          timestamp: $timestamp,
+        })
+      }
+      None => value,
+    };
 
     #[allow(clippy::redundant_closure_call, reason = "easier for macro")]
     Ok($result($self, value))
@@ -457,6 +1342,7 @@ impl_parse_register!(
   |register: &MeasurementRegister::<RegisterKindStorage>, storage| {
     MeasurementRegister::<RegisterValueStorage> {
       address: register.address,
+      function: register.function,
       storage,
       name: register.name.clone(),
     }
@@ -468,6 +1354,7 @@ impl_parse_register!(
   |register: &DetectRegister::<RegisterKindStorage>, storage| {
     DetectRegister::<RegisterValueStorage> {
       address: register.address,
+      function: register.function,
       storage,
       r#match: register.r#match.clone(),
     }
@@ -480,6 +1367,7 @@ impl_parse_register!(IdRegister, |register: &IdRegister::<
                                   storage| {
   IdRegister::<RegisterValueStorage> {
     address: register.address,
+    function: register.function,
     storage,
   }
 });
@@ -495,46 +1383,110 @@ impl_parse_register!(
 );
 
 macro_rules! serialize_numeric_register {
-  ($type: ty, $value: ident, $default: expr) => {{
-    let value = TryInto::<u16>::try_into(*$value).unwrap_or(0u16);
-    let bytes = value.to_ne_bytes();
-    encode_numeric_bytes(bytes).into_iter()
+  ($type: ty, $value: ident, $order: ident, $default: expr) => {{
+    let value = TryInto::<$type>::try_into(*$value).unwrap_or($default);
+    let bytes = value.to_be_bytes();
+    encode_numeric_bytes(bytes, *$order).into_iter()
   }};
 }
 
 macro_rules! serialize_register {
   ($self: ident) => {{
     match &$self.storage {
-      RegisterValueStorage::U16(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(u16, value, 0u16)
-      }
-      RegisterValueStorage::U32(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(u32, value, 0u32)
-      }
-      RegisterValueStorage::U64(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(u64, value, 0u64)
-      }
-      RegisterValueStorage::S16(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(i16, value, 0i16)
-      }
-      RegisterValueStorage::S32(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(i32, value, 0i32)
-      }
-      RegisterValueStorage::S64(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(i64, value, 0i64)
-      }
-      RegisterValueStorage::F32(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(f32, value, 0f32)
-      }
-      RegisterValueStorage::F64(RegisterValue::<Decimal> { value, .. }) => {
-        serialize_numeric_register!(f64, value, 0f64)
+      RegisterValueStorage::U16(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(u16, value, order, 0u16),
+      RegisterValueStorage::U32(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(u32, value, order, 0u32),
+      RegisterValueStorage::U64(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(u64, value, order, 0u64),
+      RegisterValueStorage::S16(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(i16, value, order, 0i16),
+      RegisterValueStorage::S32(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(i32, value, order, 0i32),
+      RegisterValueStorage::S64(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(i64, value, order, 0i64),
+      RegisterValueStorage::F32(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(f32, value, order, 0f32),
+      RegisterValueStorage::F64(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => serialize_numeric_register!(f64, value, order, 0f64),
+      RegisterValueStorage::U48(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => {
+        encode_unsigned(u64::try_from(*value).unwrap_or_default(), 3, *order)
+          .into_iter()
       }
+      RegisterValueStorage::Bcd16(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => encode_bcd(*value, 1, *order).into_iter(),
+      RegisterValueStorage::Bcd32(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => encode_bcd(*value, 2, *order).into_iter(),
+      RegisterValueStorage::Bcd64(RegisterValue::<Decimal> {
+        value,
+        order,
+        ..
+      }) => encode_bcd(*value, 4, *order).into_iter(),
+      RegisterValueStorage::DateTime(RegisterValue::<DateTimeValue> {
+        value: DateTimeValue { value, timezone },
+        order,
+        ..
+      }) => encode_schneider_datetime(*value, *timezone, *order).into_iter(),
+      RegisterValueStorage::Iec870Time(RegisterValue::<DateTimeValue> {
+        value: DateTimeValue { value, timezone },
+        order,
+        ..
+      }) => encode_iec870_time(*value, *timezone, *order).into_iter(),
       RegisterValueStorage::String(RegisterValue::<String> {
-        value, ..
-      }) => encode_string_bytes(value.as_str().bytes()).into_iter(),
+        value,
+        order,
+        ..
+      }) => encode_string_bytes(value.as_str().bytes(), *order).into_iter(),
       RegisterValueStorage::Raw(RegisterValue::<Vec<u16>> {
         value, ..
       }) => value.clone().into_iter(),
+      RegisterValueStorage::Enum(RegisterValue::<EnumValue> {
+        value: EnumValue { value, length, .. },
+        order,
+        ..
+      }) => encode_unsigned(*value, *length, *order).into_iter(),
+      RegisterValueStorage::Bitfield(RegisterValue::<BitfieldValue> {
+        value: BitfieldValue { value, length, .. },
+        order,
+        ..
+      }) => encode_unsigned(*value, *length, *order).into_iter(),
+      RegisterValueStorage::Null(RegisterValue::<Quantity> {
+        value, ..
+      }) => vec![0u16; usize::from(*value)].into_iter(),
     }
   }};
 }
@@ -565,3 +1517,153 @@ impl_record!(MeasurementRegister);
 impl_record!(DetectRegister);
 impl_record!(IdRegister);
 impl_record!(ValueRegister);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn numeric(order: Order) -> NumericRegisterKind {
+    NumericRegisterKind {
+      multiplier: None,
+      offset: None,
+      order,
+      scale_register: None,
+      not_implemented: None,
+    }
+  }
+
+  fn round_trip(kind: RegisterKindStorage, number: Decimal) -> Option<Decimal> {
+    let register = ValueRegister {
+      address: 0,
+      storage: kind.clone(),
+    };
+    register
+      .parse(kind.encode_number(number))
+      .ok()
+      .and_then(|register| register.storage.number())
+  }
+
+  #[test]
+  fn u48_round_trips() {
+    for order in [Order::Big, Order::Little, Order::WordSwapped] {
+      for value in [0u64, 1, 0x1234_5678_9ABC, U48_MAX - 1] {
+        let value = Decimal::from(value);
+        assert_eq!(
+          round_trip(RegisterKindStorage::U48(numeric(order)), value),
+          Some(value),
+          "{order:?} {value}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn bcd_round_trips() {
+    for (kind, value) in [
+      (RegisterKindStorage::Bcd16(numeric(Order::Big)), 9876u64),
+      (
+        RegisterKindStorage::Bcd32(numeric(Order::WordSwapped)),
+        12_345_678,
+      ),
+      (
+        RegisterKindStorage::Bcd64(numeric(Order::Big)),
+        1_234_567_890_123,
+      ),
+    ] {
+      let value = Decimal::from(value);
+      assert_eq!(round_trip(kind, value), Some(value), "{value}");
+    }
+  }
+
+  #[test]
+  fn bcd_overflow_encodes_zero() {
+    let kind = RegisterKindStorage::Bcd16(numeric(Order::Big));
+    assert_eq!(kind.encode_number(Decimal::from(10_000)), vec![0]);
+  }
+
+  #[test]
+  fn date_times_round_trip() {
+    #[allow(clippy::unwrap_used, reason = "test date is valid")]
+    let value = chrono::Utc
+      .with_ymd_and_hms(2024, 6, 30, 21, 15, 42)
+      .unwrap();
+    let kind = DateTimeRegisterKind {
+      timezone: chrono_tz::Europe::Zagreb,
+      order: Order::Big,
+    };
+    for kind in [
+      RegisterKindStorage::DateTime(kind),
+      RegisterKindStorage::Iec870Time(kind),
+    ] {
+      let register = ValueRegister {
+        address: 0,
+        storage: kind.clone(),
+      };
+      let parsed = register
+        .parse(kind.encode_date_time(Some(value)))
+        .ok()
+        .and_then(|register| match register.storage {
+          RegisterValueStorage::DateTime(storage)
+          | RegisterValueStorage::Iec870Time(storage) => storage.value.value,
+          _ => None,
+        });
+      assert_eq!(parsed, Some(value), "{kind:?}");
+    }
+  }
+
+  fn measurement(
+    name: &str,
+    address: Address,
+    storage: RegisterKindStorage,
+  ) -> MeasurementRegister<RegisterKindStorage> {
+    MeasurementRegister {
+      address,
+      function: Function::HoldingRegisters,
+      storage,
+      name: name.to_owned(),
+    }
+  }
+
+  #[test]
+  fn scale_registers_of_other_groups_are_read_along() {
+    let group = vec![measurement(
+      "power",
+      10,
+      RegisterKindStorage::U16(NumericRegisterKind {
+        scale_register: Some(20),
+        ..numeric(Order::Big)
+      }),
+    )];
+    let scale =
+      measurement("scale", 20, RegisterKindStorage::S16(numeric(Order::Big)));
+    let others = [
+      scale.clone(),
+      measurement("energy", 30, RegisterKindStorage::U16(numeric(Order::Big))),
+      scale.clone(),
+    ];
+
+    let referenced = referenced_scale_registers(&group, others.iter());
+    assert_eq!(
+      referenced
+        .iter()
+        .map(|register| register.name.as_str())
+        .collect::<Vec<_>>(),
+      vec!["scale"]
+    );
+
+    let kinds = group.iter().chain(referenced.iter()).collect::<Vec<_>>();
+    #[allow(clippy::unwrap_used, reason = "words fit the registers")]
+    let mut registers = kinds
+      .iter()
+      .zip([vec![1234], vec![0xFFFE]])
+      .map(|(kind, words)| kind.parse(words).unwrap())
+      .collect::<Vec<_>>();
+    apply_scale_registers(&group, &mut registers);
+    assert_eq!(
+      registers
+        .first()
+        .and_then(|register| register.storage.number()),
+      Some(Decimal::new(1234, 2))
+    );
+  }
+}
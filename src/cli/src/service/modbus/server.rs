@@ -0,0 +1,34 @@
+use tokio_modbus::{Request, Response};
+
+use super::connection::ExceptionCode;
+
+// NOTE: tokio-modbus 0.9 doesn't export its exception response so servers
+// send exceptions as custom responses with the error bit of the function set
+
+pub(crate) fn exception(function: u8, code: ExceptionCode) -> Response {
+  Response::Custom(
+    function | 0x80,
+    tokio_modbus::bytes::Bytes::from(vec![code.code()]),
+  )
+}
+
+pub(crate) fn function_code(request: &Request<'_>) -> u8 {
+  match request {
+    Request::ReadCoils(..) => 0x01,
+    Request::ReadDiscreteInputs(..) => 0x02,
+    Request::ReadHoldingRegisters(..) => 0x03,
+    Request::ReadInputRegisters(..) => 0x04,
+    Request::WriteSingleCoil(..) => 0x05,
+    Request::WriteSingleRegister(..) => 0x06,
+    Request::WriteMultipleCoils(..) => 0x0F,
+    Request::WriteMultipleRegisters(..) => 0x10,
+    Request::MaskWriteRegister(..) => 0x16,
+    Request::ReadWriteMultipleRegisters(..) => 0x17,
+    Request::Custom(function, _) => *function,
+    Request::Disconnect => 0x00,
+  }
+}
+
+pub(crate) fn words_to_bits(words: Vec<u16>) -> Vec<bool> {
+  words.into_iter().map(|word| word != 0).collect()
+}
@@ -3,12 +3,14 @@ use std::{collections::HashMap, sync::Arc};
 use futures::StreamExt;
 use futures_core::Stream;
 use tokio::sync::Mutex;
+use tokio_modbus::Quantity;
 
 use crate::*;
 
 use super::batch::*;
 use super::connection::Destination;
 use super::connection::Device;
+use super::connection::Exception;
 use super::record::Record;
 use super::span::*;
 use super::worker::*;
@@ -27,6 +29,8 @@ pub(crate) struct Service {
   termination_timeout: chrono::Duration,
   congestion_backoff: chrono::Duration,
   partial_retries: u32,
+  turnaround_delay: chrono::Duration,
+  rtu_over_tcp_line: super::connection::Line,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -39,6 +43,9 @@ pub(crate) enum ServerReadError {
 
   #[error("Parsing failure")]
   ParsingFailed(anyhow::Error),
+
+  #[error("Device responded with an exception")]
+  Exception(Exception),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -48,6 +55,9 @@ pub(crate) enum ServerWriteError {
 
   #[error("Server failure")]
   ServerFailed(anyhow::Error),
+
+  #[error("Device responded with an exception")]
+  Exception(Exception),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -93,14 +103,78 @@ impl service::Service for Service {
       termination_timeout: config.modbus.termination_timeout,
       congestion_backoff: config.modbus.congestion_backoff,
       partial_retries: config.modbus.partial_retries,
+      turnaround_delay: config.modbus.turnaround_delay,
+      rtu_over_tcp_line: config.modbus.rtu_over_tcp_line,
     }
   }
 }
 
 impl Service {
+  // NOTE: workers only read their settings when they are created so they get
+  // replaced and the old ones terminated which ends their streams
+  #[tracing::instrument(skip(self, config))]
+  pub(crate) async fn reconfigure(&self, config: config::Values) -> Self {
+    let service = Self {
+      devices: self.devices.clone(),
+      servers: self.servers.clone(),
+      request_timeout: config.modbus.request_timeout,
+      batch_threshold: config.modbus.batch_threshold,
+      termination_timeout: config.modbus.termination_timeout,
+      congestion_backoff: config.modbus.congestion_backoff,
+      partial_retries: config.modbus.partial_retries,
+      turnaround_delay: config.modbus.turnaround_delay,
+      rtu_over_tcp_line: config.modbus.rtu_over_tcp_line,
+    };
+
+    let workers_changed = service.request_timeout != self.request_timeout
+      || service.termination_timeout != self.termination_timeout
+      || service.congestion_backoff != self.congestion_backoff
+      || service.partial_retries != self.partial_retries
+      || service.turnaround_delay != self.turnaround_delay
+      || service.rtu_over_tcp_line != self.rtu_over_tcp_line;
+    if !workers_changed {
+      return service;
+    }
+
+    let mut old_servers = Vec::new();
+    {
+      let mut servers = self.servers.clone().lock_owned().await;
+      let mut devices = self.devices.clone().lock_owned().await;
+      for (device, server) in servers.iter_mut() {
+        let worker = service.new_worker();
+        for designation in devices.values_mut() {
+          if &designation.destination.device == device {
+            designation.worker = worker.clone();
+          }
+        }
+        old_servers.push(std::mem::replace(server, Server { worker }));
+      }
+
+      tracing::trace!("Replaced workers for {:?}", servers.keys());
+    }
+
+    for server in old_servers {
+      if let Err(error) = server.worker.terminate().await {
+        // NOTE: error -> trace because this means it already terminated and disconnected
+        tracing::trace!("Failed terminating server worker {}", error)
+      }
+    }
+
+    service
+  }
+
   #[tracing::instrument(skip(self))]
-  pub(crate) async fn bind(&self, id: String, destination: Destination) {
+  pub(crate) async fn bind(
+    &self,
+    id: String,
+    destination: Destination,
+    max_batch_quantity: Option<Quantity>,
+    turnaround_delay: Option<chrono::Duration>,
+  ) {
     let server = self.get_server(destination.clone()).await;
+    if let Some(slave) = destination.slave {
+      server.worker.set_turnaround(slave, turnaround_delay);
+    }
     {
       let mut devices = self.devices.clone().lock_owned().await;
       devices.insert(
@@ -108,6 +182,7 @@ impl Service {
         Designation {
           worker: server.worker,
           destination,
+          max_batch_quantity,
         },
       );
 
@@ -115,6 +190,17 @@ impl Service {
     }
   }
 
+  // NOTE: returns the device as it was bound because rtu devices with
+  // different lines on the same path are considered equal
+  #[tracing::instrument(skip(self))]
+  pub(crate) async fn bound_device(&self, device: &Device) -> Option<Device> {
+    let devices = self.devices.clone().lock_owned().await;
+    devices
+      .values()
+      .find(|designation| &designation.destination.device == device)
+      .map(|designation| designation.destination.device.clone())
+  }
+
   #[tracing::instrument(skip(self))]
   pub(crate) async fn stop_from_id(&self, id: &str) {
     let mut server_to_remove = None;
@@ -238,10 +324,11 @@ impl Service {
     &self,
     destination: Destination,
     spans: TIntoIterator,
+    max_batch_quantity: Option<Quantity>,
   ) -> Result<ReadResponse<TSpan>, ServerReadError> {
     let server = self.get_server(destination.clone()).await;
     let response = self
-      .read_from_worker(server.worker, destination, spans)
+      .read_from_worker(server.worker, destination, spans, max_batch_quantity)
       .await?;
 
     tracing::trace!("Read {:?} spans", response.len());
@@ -279,13 +366,21 @@ impl Service {
     &self,
     destination: Destination,
     spans: TIntoIterator,
+    max_batch_quantity: Option<Quantity>,
+    interval: Option<chrono::Duration>,
   ) -> Result<
     impl Stream<Item = Result<Vec<TSpan>, ServerReadError>>,
     ServerStreamError,
   > {
     let server = self.get_server(destination.clone()).await;
     let stream = self
-      .stream_from_worker(server.worker, destination, spans)
+      .stream_from_worker(
+        server.worker,
+        destination,
+        spans,
+        max_batch_quantity,
+        interval,
+      )
       .await?;
 
     tracing::trace!("Streaming spans");
@@ -309,7 +404,12 @@ impl Service {
       None => return Err(DeviceReadError::DeviceNotFound(id.to_string())),
     };
     let response = self
-      .read_from_worker(device.worker, device.destination, spans)
+      .read_from_worker(
+        device.worker,
+        device.destination,
+        spans,
+        device.max_batch_quantity,
+      )
       .await?;
 
     tracing::trace!("Read {:?} spans", response.len());
@@ -350,6 +450,7 @@ impl Service {
     &self,
     id: &str,
     spans: TIntoIterator,
+    interval: Option<chrono::Duration>,
   ) -> Result<
     impl Stream<Item = Result<Vec<TSpan>, ServerReadError>>,
     DeviceStreamError,
@@ -359,7 +460,13 @@ impl Service {
       None => return Err(DeviceStreamError::DeviceNotFound(id.to_string())),
     };
     let stream = self
-      .stream_from_worker(device.worker, device.destination, spans)
+      .stream_from_worker(
+        device.worker,
+        device.destination,
+        spans,
+        device.max_batch_quantity,
+        interval,
+      )
       .await?;
 
     tracing::trace!("Streaming spans");
@@ -377,11 +484,14 @@ impl Service {
     worker: Worker,
     destination: Destination,
     spans: TIntoIterator,
+    max_batch_quantity: Option<Quantity>,
   ) -> Result<ReadResponse<TSpan>, ServerReadError> {
     let iter = spans.into_iter();
     let len = iter.len();
-    let batches = batch_spans(iter, self.batch_threshold);
-    let result = worker.read(destination, batches.iter()).await;
+    let batches = batch_spans(iter, self.batch_threshold, max_batch_quantity);
+    let result = worker
+      .read(destination, batches.iter().map(Batch::simplify))
+      .await;
     let response = Self::parse_worker_read_response(result, batches, len)?;
     Ok(response)
   }
@@ -412,15 +522,21 @@ impl Service {
     worker: Worker,
     destination: Destination,
     spans: TIntoIterator,
+    max_batch_quantity: Option<Quantity>,
+    interval: Option<chrono::Duration>,
   ) -> Result<
     impl Stream<Item = Result<ReadResponse<TSpan>, ServerReadError>>,
     ServerStreamError,
   > {
     let iter = spans.into_iter();
     let len = iter.len();
-    let batches = batch_spans(iter, self.batch_threshold);
+    let batches = batch_spans(iter, self.batch_threshold, max_batch_quantity);
     let stream = match worker
-      .stream(destination, batches.clone().into_iter())
+      .stream(
+        destination,
+        batches.iter().map(Batch::simplify).collect::<Vec<_>>(),
+        interval,
+      )
       .await
     {
       Ok(stream) => stream,
@@ -450,11 +566,15 @@ impl Service {
         super::worker::SendError::ChannelDisconnected(error) => {
           return Err(ServerReadError::ServerFailed(error))
         }
+        super::worker::SendError::Exception(error) => {
+          return Err(ServerReadError::Exception(error))
+        }
       },
     };
 
     let mut response = Vec::with_capacity(len);
-    for (parser, data) in batches.into_iter().zip(data.into_iter()) {
+    for (parser, data) in batches.into_iter().zip(data) {
+      let parser = without_missing(parser, &data.missing);
       let mut parsed =
         match parser.parse_with_timestamp(data.inner, data.timestamp) {
           Ok(parsed) => parsed,
@@ -478,6 +598,9 @@ impl Service {
         super::worker::SendError::ChannelDisconnected(error) => {
           return Err(ServerWriteError::ServerFailed(error))
         }
+        super::worker::SendError::Exception(error) => {
+          return Err(ServerWriteError::Exception(error))
+        }
       },
     };
 
@@ -489,17 +612,31 @@ impl Service {
     let worker = workers
       .entry(destination.device)
       .or_insert_with(|| Server {
-        worker: Worker::new(
-          self.request_timeout,
-          self.termination_timeout,
-          self.congestion_backoff,
-          self.partial_retries,
-        ),
+        worker: self.new_worker(),
       })
       .clone();
     worker
   }
 
+  fn new_worker(&self) -> Worker {
+    Worker::new(
+      self.request_timeout,
+      self.termination_timeout,
+      self.congestion_backoff,
+      self.partial_retries,
+      self.turnaround_delay,
+      self.rtu_over_tcp_line,
+    )
+  }
+
+  #[tracing::instrument(skip(self))]
+  pub(crate) async fn desyncs_from_id(&self, id: &str) -> Option<u64> {
+    self
+      .get_device(id)
+      .await
+      .map(|device| device.worker.desyncs())
+  }
+
   async fn get_device(&self, id: &str) -> Option<Designation> {
     let devices = self.devices.clone().lock_owned().await;
     let device = devices.get(id).cloned();
@@ -507,6 +644,34 @@ impl Service {
   }
 }
 
+// NOTE: spans with illegal addresses are left out of the response so
+// callers that need every span have to compare lengths
+fn without_missing<TSpan: Span>(
+  batch: Batch<TSpan>,
+  missing: &[SimpleSpan],
+) -> Batch<TSpan> {
+  if missing.is_empty() {
+    return batch;
+  }
+
+  Batch {
+    address: batch.address,
+    quantity: batch.quantity,
+    function: batch.function,
+    inner: batch
+      .inner
+      .into_iter()
+      .filter(|span| {
+        !missing.contains(&SimpleSpan {
+          address: span.address(),
+          quantity: span.quantity(),
+          function: span.function(),
+        })
+      })
+      .collect(),
+  }
+}
+
 #[derive(Clone, Debug)]
 struct Server {
   worker: Worker,
@@ -516,4 +681,5 @@ struct Server {
 struct Designation {
   worker: Worker,
   destination: Destination,
+  max_batch_quantity: Option<Quantity>,
 }
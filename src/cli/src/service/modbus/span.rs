@@ -3,10 +3,23 @@ use std::fmt::Debug;
 use either::Either;
 use tokio_modbus::{Address, Quantity};
 
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum Function {
+  HoldingRegisters,
+  InputRegisters,
+  Coils,
+  DiscreteInputs,
+  // NOTE: the address is the object id and the quantity is the number
+  // of words the object value is padded or truncated to
+  DeviceIdentification,
+}
+
 pub(crate) trait Span {
   fn address(&self) -> Address;
 
   fn quantity(&self) -> Quantity;
+
+  fn function(&self) -> Function;
 }
 
 pub(crate) trait SpanParser<TParsed: Span> {
@@ -33,6 +46,7 @@ pub(crate) trait SpanParser<TParsed: Span> {
 pub(crate) struct SimpleSpan {
   pub(crate) address: u16,
   pub(crate) quantity: u16,
+  pub(crate) function: Function,
 }
 
 impl Span for SimpleSpan {
@@ -43,6 +57,10 @@ impl Span for SimpleSpan {
   fn quantity(&self) -> Quantity {
     self.quantity
   }
+
+  fn function(&self) -> Function {
+    self.function
+  }
 }
 
 impl<TLeftSpan: Span, TRightSpan: Span> Span for Either<TLeftSpan, TRightSpan> {
@@ -59,6 +77,13 @@ impl<TLeftSpan: Span, TRightSpan: Span> Span for Either<TLeftSpan, TRightSpan> {
       Either::Right(span) => span.quantity(),
     }
   }
+
+  fn function(&self) -> Function {
+    match self {
+      Either::Left(span) => span.function(),
+      Either::Right(span) => span.function(),
+    }
+  }
 }
 
 impl<
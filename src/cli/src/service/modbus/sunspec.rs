@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use either::Either;
+use serde::{Deserialize, Serialize};
+use tokio_modbus::{Address, Quantity};
+
+use crate::config;
+
+use super::encoding::Order;
+use super::register::*;
+use super::span::Function;
+
+// NOTE: https://sunspec.org/specifications models are laid out as a chain
+// of id and length headers right after the marker at one of the base addresses
+
+pub(crate) const KIND: &str = "sunspec";
+
+pub(crate) const BASE_ADDRESSES: [Address; 3] = [40000, 0, 50000];
+
+pub(crate) const END: u16 = 0xFFFF;
+
+pub(crate) const HEADER_LENGTH: Quantity = 2;
+
+// NOTE: guards against walking garbage forever on devices that happen
+// to have the marker but no proper model chain
+pub(crate) const MAX_MODELS: usize = 64;
+
+const COMMON_MODEL: u16 = 1;
+
+const COMMON_SERIAL_NUMBER_OFFSET: Address = 48;
+
+const COMMON_SERIAL_NUMBER_LENGTH: Quantity = 16;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Model {
+  pub(crate) id: u16,
+  // NOTE: address of the first register after the model header
+  pub(crate) address: Address,
+  pub(crate) length: Quantity,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Chain {
+  pub(crate) base: Address,
+  pub(crate) models: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PointKind {
+  U16,
+  S16,
+  U32,
+  Acc32,
+  F32,
+  Enum16(&'static [(u64, &'static str)]),
+  Bitfield32(&'static [(u8, &'static str)]),
+  ScaleFactor,
+}
+
+const INVERTER_STATES: &[(u64, &str)] = &[
+  (1, "OFF"),
+  (2, "SLEEPING"),
+  (3, "STARTING"),
+  (4, "MPPT"),
+  (5, "THROTTLED"),
+  (6, "SHUTTING_DOWN"),
+  (7, "FAULT"),
+  (8, "STANDBY"),
+];
+
+const INVERTER_EVENTS: &[(u8, &str)] = &[
+  (0, "GROUND_FAULT"),
+  (1, "DC_OVER_VOLT"),
+  (2, "AC_DISCONNECT"),
+  (3, "DC_DISCONNECT"),
+  (4, "GRID_DISCONNECT"),
+  (5, "CABINET_OPEN"),
+  (6, "MANUAL_SHUTDOWN"),
+  (7, "OVER_TEMP"),
+  (8, "OVER_FREQUENCY"),
+  (9, "UNDER_FREQUENCY"),
+  (10, "AC_OVER_VOLT"),
+  (11, "AC_UNDER_VOLT"),
+  (12, "BLOWN_STRING_FUSE"),
+  (13, "UNDER_TEMP"),
+  (14, "MEMORY_LOSS"),
+  (15, "HW_TEST_FAILURE"),
+];
+
+const METER_EVENTS: &[(u8, &str)] = &[
+  (2, "POWER_FAILURE"),
+  (3, "UNDER_VOLTAGE"),
+  (4, "LOW_PF"),
+  (5, "OVER_CURRENT"),
+  (6, "OVER_VOLTAGE"),
+  (7, "MISSING_SENSOR"),
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Point {
+  name: &'static str,
+  offset: Address,
+  kind: PointKind,
+  scale: Option<&'static str>,
+}
+
+const fn point(
+  name: &'static str,
+  offset: Address,
+  kind: PointKind,
+  scale: Option<&'static str>,
+) -> Point {
+  Point {
+    name,
+    offset,
+    kind,
+    scale,
+  }
+}
+
+const INVERTER_POINTS: &[Point] = &[
+  point("A", 0, PointKind::U16, Some("A_SF")),
+  point("AphA", 1, PointKind::U16, Some("A_SF")),
+  point("AphB", 2, PointKind::U16, Some("A_SF")),
+  point("AphC", 3, PointKind::U16, Some("A_SF")),
+  point("A_SF", 4, PointKind::ScaleFactor, None),
+  point("PPVphAB", 5, PointKind::U16, Some("V_SF")),
+  point("PPVphBC", 6, PointKind::U16, Some("V_SF")),
+  point("PPVphCA", 7, PointKind::U16, Some("V_SF")),
+  point("PhVphA", 8, PointKind::U16, Some("V_SF")),
+  point("PhVphB", 9, PointKind::U16, Some("V_SF")),
+  point("PhVphC", 10, PointKind::U16, Some("V_SF")),
+  point("V_SF", 11, PointKind::ScaleFactor, None),
+  point("W", 12, PointKind::S16, Some("W_SF")),
+  point("W_SF", 13, PointKind::ScaleFactor, None),
+  point("Hz", 14, PointKind::U16, Some("Hz_SF")),
+  point("Hz_SF", 15, PointKind::ScaleFactor, None),
+  point("VA", 16, PointKind::S16, Some("VA_SF")),
+  point("VA_SF", 17, PointKind::ScaleFactor, None),
+  point("VAr", 18, PointKind::S16, Some("VAr_SF")),
+  point("VAr_SF", 19, PointKind::ScaleFactor, None),
+  point("PF", 20, PointKind::S16, Some("PF_SF")),
+  point("PF_SF", 21, PointKind::ScaleFactor, None),
+  point("WH", 22, PointKind::Acc32, Some("WH_SF")),
+  point("WH_SF", 24, PointKind::ScaleFactor, None),
+  point("DCA", 25, PointKind::U16, Some("DCA_SF")),
+  point("DCA_SF", 26, PointKind::ScaleFactor, None),
+  point("DCV", 27, PointKind::U16, Some("DCV_SF")),
+  point("DCV_SF", 28, PointKind::ScaleFactor, None),
+  point("DCW", 29, PointKind::S16, Some("DCW_SF")),
+  point("DCW_SF", 30, PointKind::ScaleFactor, None),
+  point("TmpCab", 31, PointKind::S16, Some("Tmp_SF")),
+  point("TmpSnk", 32, PointKind::S16, Some("Tmp_SF")),
+  point("TmpTrns", 33, PointKind::S16, Some("Tmp_SF")),
+  point("TmpOt", 34, PointKind::S16, Some("Tmp_SF")),
+  point("Tmp_SF", 35, PointKind::ScaleFactor, None),
+  point("St", 36, PointKind::Enum16(INVERTER_STATES), None),
+  point("StVnd", 37, PointKind::U16, None),
+  point("Evt1", 38, PointKind::Bitfield32(INVERTER_EVENTS), None),
+  point("Evt2", 40, PointKind::U32, None),
+  point("EvtVnd1", 42, PointKind::U32, None),
+  point("EvtVnd2", 44, PointKind::U32, None),
+  point("EvtVnd3", 46, PointKind::U32, None),
+  point("EvtVnd4", 48, PointKind::U32, None),
+];
+
+const FLOAT_INVERTER_POINTS: &[Point] = &[
+  point("A", 0, PointKind::F32, None),
+  point("AphA", 2, PointKind::F32, None),
+  point("AphB", 4, PointKind::F32, None),
+  point("AphC", 6, PointKind::F32, None),
+  point("PPVphAB", 8, PointKind::F32, None),
+  point("PPVphBC", 10, PointKind::F32, None),
+  point("PPVphCA", 12, PointKind::F32, None),
+  point("PhVphA", 14, PointKind::F32, None),
+  point("PhVphB", 16, PointKind::F32, None),
+  point("PhVphC", 18, PointKind::F32, None),
+  point("W", 20, PointKind::F32, None),
+  point("Hz", 22, PointKind::F32, None),
+  point("VA", 24, PointKind::F32, None),
+  point("VAr", 26, PointKind::F32, None),
+  point("PF", 28, PointKind::F32, None),
+  point("WH", 30, PointKind::F32, None),
+  point("DCA", 32, PointKind::F32, None),
+  point("DCV", 34, PointKind::F32, None),
+  point("DCW", 36, PointKind::F32, None),
+  point("TmpCab", 38, PointKind::F32, None),
+  point("TmpSnk", 40, PointKind::F32, None),
+  point("TmpTrns", 42, PointKind::F32, None),
+  point("TmpOt", 44, PointKind::F32, None),
+  point("St", 46, PointKind::Enum16(INVERTER_STATES), None),
+  point("StVnd", 47, PointKind::U16, None),
+  point("Evt1", 48, PointKind::Bitfield32(INVERTER_EVENTS), None),
+  point("Evt2", 50, PointKind::U32, None),
+  point("EvtVnd1", 52, PointKind::U32, None),
+  point("EvtVnd2", 54, PointKind::U32, None),
+  point("EvtVnd3", 56, PointKind::U32, None),
+  point("EvtVnd4", 58, PointKind::U32, None),
+];
+
+const METER_POINTS: &[Point] = &[
+  point("A", 0, PointKind::S16, Some("A_SF")),
+  point("AphA", 1, PointKind::S16, Some("A_SF")),
+  point("AphB", 2, PointKind::S16, Some("A_SF")),
+  point("AphC", 3, PointKind::S16, Some("A_SF")),
+  point("A_SF", 4, PointKind::ScaleFactor, None),
+  point("PhV", 5, PointKind::S16, Some("V_SF")),
+  point("PhVphA", 6, PointKind::S16, Some("V_SF")),
+  point("PhVphB", 7, PointKind::S16, Some("V_SF")),
+  point("PhVphC", 8, PointKind::S16, Some("V_SF")),
+  point("PPV", 9, PointKind::S16, Some("V_SF")),
+  point("PPVphAB", 10, PointKind::S16, Some("V_SF")),
+  point("PPVphBC", 11, PointKind::S16, Some("V_SF")),
+  point("PPVphCA", 12, PointKind::S16, Some("V_SF")),
+  point("V_SF", 13, PointKind::ScaleFactor, None),
+  point("Hz", 14, PointKind::S16, Some("Hz_SF")),
+  point("Hz_SF", 15, PointKind::ScaleFactor, None),
+  point("W", 16, PointKind::S16, Some("W_SF")),
+  point("WphA", 17, PointKind::S16, Some("W_SF")),
+  point("WphB", 18, PointKind::S16, Some("W_SF")),
+  point("WphC", 19, PointKind::S16, Some("W_SF")),
+  point("W_SF", 20, PointKind::ScaleFactor, None),
+  point("VA", 21, PointKind::S16, Some("VA_SF")),
+  point("VAphA", 22, PointKind::S16, Some("VA_SF")),
+  point("VAphB", 23, PointKind::S16, Some("VA_SF")),
+  point("VAphC", 24, PointKind::S16, Some("VA_SF")),
+  point("VA_SF", 25, PointKind::ScaleFactor, None),
+  point("VAR", 26, PointKind::S16, Some("VAR_SF")),
+  point("VARphA", 27, PointKind::S16, Some("VAR_SF")),
+  point("VARphB", 28, PointKind::S16, Some("VAR_SF")),
+  point("VARphC", 29, PointKind::S16, Some("VAR_SF")),
+  point("VAR_SF", 30, PointKind::ScaleFactor, None),
+  point("PF", 31, PointKind::S16, Some("PF_SF")),
+  point("PFphA", 32, PointKind::S16, Some("PF_SF")),
+  point("PFphB", 33, PointKind::S16, Some("PF_SF")),
+  point("PFphC", 34, PointKind::S16, Some("PF_SF")),
+  point("PF_SF", 35, PointKind::ScaleFactor, None),
+  point("TotWhExp", 36, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhExpPhA", 38, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhExpPhB", 40, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhExpPhC", 42, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhImp", 44, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhImpPhA", 46, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhImpPhB", 48, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWhImpPhC", 50, PointKind::Acc32, Some("TotWh_SF")),
+  point("TotWh_SF", 52, PointKind::ScaleFactor, None),
+  point("TotVAhExp", 53, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhExpPhA", 55, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhExpPhB", 57, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhExpPhC", 59, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhImp", 61, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhImpPhA", 63, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhImpPhB", 65, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAhImpPhC", 67, PointKind::Acc32, Some("TotVAh_SF")),
+  point("TotVAh_SF", 69, PointKind::ScaleFactor, None),
+  point("TotVArhImpQ1", 70, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ1PhA", 72, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ1PhB", 74, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ1PhC", 76, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ2", 78, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ2PhA", 80, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ2PhB", 82, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhImpQ2PhC", 84, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ3", 86, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ3PhA", 88, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ3PhB", 90, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ3PhC", 92, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ4", 94, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ4PhA", 96, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ4PhB", 98, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArhExpQ4PhC", 100, PointKind::Acc32, Some("TotVArh_SF")),
+  point("TotVArh_SF", 102, PointKind::ScaleFactor, None),
+  point("Evt", 103, PointKind::Bitfield32(METER_EVENTS), None),
+];
+
+const FLOAT_METER_POINTS: &[Point] = &[
+  point("A", 0, PointKind::F32, None),
+  point("AphA", 2, PointKind::F32, None),
+  point("AphB", 4, PointKind::F32, None),
+  point("AphC", 6, PointKind::F32, None),
+  point("PhV", 8, PointKind::F32, None),
+  point("PhVphA", 10, PointKind::F32, None),
+  point("PhVphB", 12, PointKind::F32, None),
+  point("PhVphC", 14, PointKind::F32, None),
+  point("PPV", 16, PointKind::F32, None),
+  point("PPVphAB", 18, PointKind::F32, None),
+  point("PPVphBC", 20, PointKind::F32, None),
+  point("PPVphCA", 22, PointKind::F32, None),
+  point("Hz", 24, PointKind::F32, None),
+  point("W", 26, PointKind::F32, None),
+  point("WphA", 28, PointKind::F32, None),
+  point("WphB", 30, PointKind::F32, None),
+  point("WphC", 32, PointKind::F32, None),
+  point("VA", 34, PointKind::F32, None),
+  point("VAphA", 36, PointKind::F32, None),
+  point("VAphB", 38, PointKind::F32, None),
+  point("VAphC", 40, PointKind::F32, None),
+  point("VAR", 42, PointKind::F32, None),
+  point("VARphA", 44, PointKind::F32, None),
+  point("VARphB", 46, PointKind::F32, None),
+  point("VARphC", 48, PointKind::F32, None),
+  point("PF", 50, PointKind::F32, None),
+  point("PFphA", 52, PointKind::F32, None),
+  point("PFphB", 54, PointKind::F32, None),
+  point("PFphC", 56, PointKind::F32, None),
+  point("TotWhExp", 58, PointKind::F32, None),
+  point("TotWhExpPhA", 60, PointKind::F32, None),
+  point("TotWhExpPhB", 62, PointKind::F32, None),
+  point("TotWhExpPhC", 64, PointKind::F32, None),
+  point("TotWhImp", 66, PointKind::F32, None),
+  point("TotWhImpPhA", 68, PointKind::F32, None),
+  point("TotWhImpPhB", 70, PointKind::F32, None),
+  point("TotWhImpPhC", 72, PointKind::F32, None),
+  point("TotVAhExp", 74, PointKind::F32, None),
+  point("TotVAhExpPhA", 76, PointKind::F32, None),
+  point("TotVAhExpPhB", 78, PointKind::F32, None),
+  point("TotVAhExpPhC", 80, PointKind::F32, None),
+  point("TotVAhImp", 82, PointKind::F32, None),
+  point("TotVAhImpPhA", 84, PointKind::F32, None),
+  point("TotVAhImpPhB", 86, PointKind::F32, None),
+  point("TotVAhImpPhC", 88, PointKind::F32, None),
+  point("TotVArhImpQ1", 90, PointKind::F32, None),
+  point("TotVArhImpQ1PhA", 92, PointKind::F32, None),
+  point("TotVArhImpQ1PhB", 94, PointKind::F32, None),
+  point("TotVArhImpQ1PhC", 96, PointKind::F32, None),
+  point("TotVArhImpQ2", 98, PointKind::F32, None),
+  point("TotVArhImpQ2PhA", 100, PointKind::F32, None),
+  point("TotVArhImpQ2PhB", 102, PointKind::F32, None),
+  point("TotVArhImpQ2PhC", 104, PointKind::F32, None),
+  point("TotVArhExpQ3", 106, PointKind::F32, None),
+  point("TotVArhExpQ3PhA", 108, PointKind::F32, None),
+  point("TotVArhExpQ3PhB", 110, PointKind::F32, None),
+  point("TotVArhExpQ3PhC", 112, PointKind::F32, None),
+  point("TotVArhExpQ4", 114, PointKind::F32, None),
+  point("TotVArhExpQ4PhA", 116, PointKind::F32, None),
+  point("TotVArhExpQ4PhB", 118, PointKind::F32, None),
+  point("TotVArhExpQ4PhC", 120, PointKind::F32, None),
+  point("Evt", 122, PointKind::Bitfield32(METER_EVENTS), None),
+];
+
+fn definition(model: u16) -> Option<(&'static str, &'static [Point])> {
+  match model {
+    101..=103 => Some(("inverter", INVERTER_POINTS)),
+    111..=113 => Some(("inverter", FLOAT_INVERTER_POINTS)),
+    201..=204 => Some(("meter", METER_POINTS)),
+    211..=214 => Some(("meter", FLOAT_METER_POINTS)),
+    _ => None,
+  }
+}
+
+pub(crate) fn marker_register(
+  base: Address,
+) -> DetectRegister<RegisterKindStorage> {
+  DetectRegister {
+    address: base,
+    function: Function::HoldingRegisters,
+    storage: RegisterKindStorage::String(StringRegisterKind {
+      length: HEADER_LENGTH,
+      order: Order::Big,
+    }),
+    r#match: Either::Left("SunS".to_string()),
+  }
+}
+
+pub(crate) fn header_register(
+  address: Address,
+) -> ValueRegister<RegisterKindStorage> {
+  ValueRegister {
+    address,
+    storage: RegisterKindStorage::Raw(RawRegisterKind {
+      length: HEADER_LENGTH,
+    }),
+  }
+}
+
+// NOTE: devices without the common model have no serial number
+// so there is nothing to identify them with
+pub(crate) fn device(chain: &Chain) -> Option<config::Device> {
+  let common = chain.models.iter().find(|model| model.id == COMMON_MODEL)?;
+
+  let mut labels = HashMap::<&'static str, usize>::new();
+  let mut measurement = Vec::new();
+  for model in &chain.models {
+    let (label, points) = match definition(model.id) {
+      Some(definition) => definition,
+      None => {
+        if model.id != COMMON_MODEL {
+          tracing::trace!("Skipping unknown sunspec model {}", model.id);
+        }
+        continue;
+      }
+    };
+
+    let count = labels.entry(label).or_insert(0);
+    *count = count.saturating_add(1);
+    let prefix = if *count > 1 {
+      format!("{label}{count}")
+    } else {
+      label.to_string()
+    };
+
+    for point in points {
+      let storage = match to_register_kind(model, points, point) {
+        Some(storage) => storage,
+        None => continue,
+      };
+      if point.offset.saturating_add(storage.quantity()) > model.length {
+        continue;
+      }
+      measurement.push(MeasurementRegister {
+        address: model.address.saturating_add(point.offset),
+        function: Function::HoldingRegisters,
+        storage,
+        name: format!("{prefix}_{}", point.name),
+      });
+    }
+  }
+
+  Some(config::Device {
+    kind: KIND.to_string(),
+    priority: 0,
+    max_batch_quantity: None,
+    turnaround_delay: None,
+    id: vec![IdRegister {
+      address: common.address.saturating_add(COMMON_SERIAL_NUMBER_OFFSET),
+      function: Function::HoldingRegisters,
+      storage: RegisterKindStorage::String(StringRegisterKind {
+        length: COMMON_SERIAL_NUMBER_LENGTH,
+        order: Order::Big,
+      }),
+    }],
+    detect: vec![marker_register(chain.base)],
+    alternatives: Vec::new(),
+    measurement,
+    groups: Vec::new(),
+    configuration: Vec::new(),
+    daily: Vec::new(),
+    nightly: Vec::new(),
+    time: None,
+  })
+}
+
+fn to_register_kind(
+  model: &Model,
+  points: &[Point],
+  point: &Point,
+) -> Option<RegisterKindStorage> {
+  let scale_register = match point.scale {
+    Some(scale) => Some(
+      model.address.saturating_add(
+        points
+          .iter()
+          .find(|candidate| candidate.name == scale)?
+          .offset,
+      ),
+    ),
+    None => None,
+  };
+  let kind = NumericRegisterKind {
+    multiplier: None,
+    offset: None,
+    order: Order::Big,
+    scale_register,
+    not_implemented: not_implemented(point.kind),
+  };
+
+  Some(match point.kind {
+    PointKind::U16 => RegisterKindStorage::U16(kind),
+    PointKind::S16 | PointKind::ScaleFactor => RegisterKindStorage::S16(kind),
+    PointKind::U32 | PointKind::Acc32 => RegisterKindStorage::U32(kind),
+    PointKind::F32 => RegisterKindStorage::F32(kind),
+    PointKind::Enum16(variants) => {
+      RegisterKindStorage::Enum(EnumRegisterKind {
+        length: 1,
+        order: Order::Big,
+        variants: Arc::new(
+          variants
+            .iter()
+            .map(|(value, name)| (*value, (*name).to_string()))
+            .collect(),
+        ),
+      })
+    }
+    PointKind::Bitfield32(flags) => {
+      RegisterKindStorage::Bitfield(BitfieldRegisterKind {
+        length: 2,
+        order: Order::Big,
+        flags: Arc::new(
+          flags
+            .iter()
+            .map(|(bit, name)| (*bit, (*name).to_string()))
+            .collect(),
+        ),
+      })
+    }
+  })
+}
+
+// NOTE: enums and bitfields keep their not implemented values as is
+// because they already show up as unnamed variants and flags
+fn not_implemented(kind: PointKind) -> Option<u64> {
+  match kind {
+    PointKind::U16 => Some(0xFFFF),
+    PointKind::S16 | PointKind::ScaleFactor => Some(0x8000),
+    PointKind::U32 => Some(0xFFFF_FFFF),
+    PointKind::Acc32 => Some(0),
+    PointKind::F32 => Some(0x7FC0_0000),
+    PointKind::Enum16(_) | PointKind::Bitfield32(_) => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::span::SpanParser;
+  use super::*;
+
+  #[test]
+  fn marker_matches_decoded_string() {
+    #[allow(clippy::unwrap_used, reason = "marker is two registers")]
+    let marker = marker_register(BASE_ADDRESSES[0])
+      .parse([0x5375, 0x6E53])
+      .unwrap();
+    assert!(marker.matches());
+  }
+
+  #[test]
+  fn not_implemented_points_parse_to_null() {
+    let model = Model {
+      id: 101,
+      address: 40002,
+      length: 50,
+    };
+    for (kind, data, null) in [
+      (PointKind::U16, vec![0xFFFF], true),
+      (PointKind::U16, vec![0x0000], false),
+      (PointKind::S16, vec![0x8000], true),
+      (PointKind::U32, vec![0xFFFF, 0xFFFF], true),
+      (PointKind::Acc32, vec![0x0000, 0x0000], true),
+      (PointKind::Acc32, vec![0x0000, 0x0001], false),
+    ] {
+      let point = point("test", 0, kind, None);
+      #[allow(clippy::unwrap_used, reason = "numeric points have kinds")]
+      let register = MeasurementRegister {
+        address: model.address,
+        function: Function::HoldingRegisters,
+        storage: to_register_kind(&model, &[point], &point).unwrap(),
+        name: point.name.to_string(),
+      };
+      #[allow(clippy::unwrap_used, reason = "data matches the kind length")]
+      let value = register.parse(data.clone()).unwrap();
+      assert_eq!(
+        matches!(value.storage, RegisterValueStorage::Null(_)),
+        null,
+        "{kind:?} {data:?}"
+      );
+    }
+  }
+}
@@ -0,0 +1,63 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UdpSocket;
+
+// NOTE: modbus udp uses the same MBAP framing as tcp with one ADU per datagram
+// so a connected socket is enough to reuse the tcp client codec
+
+#[derive(Debug)]
+pub(crate) struct UdpStream {
+  socket: UdpSocket,
+}
+
+impl UdpStream {
+  pub(crate) async fn connect(
+    address: SocketAddr,
+  ) -> Result<Self, std::io::Error> {
+    let local: SocketAddr = match address {
+      SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+      SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(address).await?;
+
+    Ok(Self { socket })
+  }
+}
+
+impl AsyncRead for UdpStream {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<std::io::Result<()>> {
+    self.socket.poll_recv(cx, buf)
+  }
+}
+
+impl AsyncWrite for UdpStream {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &[u8],
+  ) -> Poll<std::io::Result<usize>> {
+    self.socket.poll_send(cx, buf)
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    _cx: &mut Context<'_>,
+  ) -> Poll<std::io::Result<()>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_shutdown(
+    self: Pin<&mut Self>,
+    _cx: &mut Context<'_>,
+  ) -> Poll<std::io::Result<()>> {
+    Poll::Ready(Ok(()))
+  }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::ops::IndexMut;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use either::Either;
@@ -7,13 +8,17 @@ use futures::Stream;
 use futures_time::future::FutureExt;
 use tokio::sync::Mutex;
 
+use super::batch::Batch;
 use super::connection::*;
 use super::record::{Record, SimpleRecord};
-use super::span::{SimpleSpan, Span};
+use super::span::SimpleSpan;
 
 // NOTE: discovery Read(Custom { kind: Other, error: ExceptionResponse { function: 3, exception: IllegalDataAddress } })
 // NOTE: timeout clog Read(Custom { kind: InvalidData, error: \"Invalid response header: expected/request = Header { transaction_id: 0, unit_id: 255 }, actual/response = Header { transaction_id: 0, unit_id: 2 }\" })
+// NOTE: timeout clogs are handled by discarding connections after timeouts and counted as desyncs
 // NOTE: timeout Timeout(Custom { kind: TimedOut, error: \"future timed out\" })
+// NOTE: batches failing with IllegalDataAddress are split and remembered per destination
+// NOTE: spans failing with IllegalDataAddress on their own are remembered as missing and dropped
 
 // TODO: shorten this thing - 1k lines is insane
 // OPTIMIZE: remove copying when reading
@@ -23,6 +28,7 @@ use super::span::{SimpleSpan, Span};
 pub(crate) struct ReadResponseEntry {
   pub(crate) inner: super::connection::ReadResponse,
   pub(crate) timestamp: chrono::DateTime<chrono::Utc>,
+  pub(crate) missing: Vec<SimpleSpan>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +46,8 @@ pub(crate) struct Worker {
   sender: RequestSender,
   handle: Arc<Mutex<Option<TaskHandle>>>,
   termination_timeout: futures_time::time::Duration,
+  turnarounds: Turnarounds,
+  desyncs: Arc<AtomicU64>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -47,6 +55,9 @@ pub(crate) enum SendError {
   #[error("Failed to connect")]
   FailedToConnect(#[from] ConnectError),
 
+  #[error("Device responded with an exception")]
+  Exception(Exception),
+
   #[error("Channel was disconnected before the request could be finished")]
   ChannelDisconnected(anyhow::Error),
 }
@@ -75,13 +86,25 @@ impl Worker {
     termination_timeout: chrono::Duration,
     congestion_backoff: chrono::Duration,
     partial_retries: u32,
+    turnaround_delay: chrono::Duration,
+    rtu_over_tcp_line: Line,
   ) -> Self {
     let (sender, receiver) = flume::unbounded();
+    let turnarounds = Turnarounds::default();
+    let desyncs = Arc::new(AtomicU64::new(0));
     let task = Task::new(
       request_timeout,
       receiver,
       congestion_backoff,
       partial_retries,
+      BusConfig {
+        turnaround: tokio::time::Duration::from_millis(
+          turnaround_delay.num_milliseconds() as u64,
+        ),
+        turnarounds: turnarounds.clone(),
+        rtu_over_tcp_line,
+      },
+      desyncs.clone(),
     );
     let handle = tokio::spawn(task.execute());
     Self {
@@ -90,14 +113,44 @@ impl Worker {
       termination_timeout: futures_time::time::Duration::from_millis(
         termination_timeout.num_milliseconds() as u64,
       ),
+      turnarounds,
+      desyncs,
+    }
+  }
+
+  // NOTE: running total since the worker was created
+  pub(crate) fn desyncs(&self) -> u64 {
+    self.desyncs.load(Ordering::Relaxed)
+  }
+
+  pub(crate) fn set_turnaround(
+    &self,
+    slave: u8,
+    turnaround: Option<chrono::Duration>,
+  ) {
+    let mut turnarounds = match self.turnarounds.write() {
+      Ok(turnarounds) => turnarounds,
+      Err(poisoned) => poisoned.into_inner(),
+    };
+    match turnaround {
+      Some(turnaround) => {
+        turnarounds.insert(
+          slave,
+          tokio::time::Duration::from_millis(
+            turnaround.num_milliseconds() as u64
+          ),
+        );
+      }
+      None => {
+        turnarounds.remove(&slave);
+      }
     }
   }
 }
 
 impl Worker {
   pub(crate) async fn read<
-    TSpan: Span,
-    TIntoIterator: IntoIterator<Item = TSpan>,
+    TIntoIterator: IntoIterator<Item = Batch<SimpleSpan>>,
   >(
     &self,
     destination: Destination,
@@ -153,12 +206,12 @@ impl Worker {
   }
 
   pub(crate) async fn stream<
-    TSpan: Span,
-    TIntoIterator: IntoIterator<Item = TSpan>,
+    TIntoIterator: IntoIterator<Item = Batch<SimpleSpan>>,
   >(
     &self,
     destination: Destination,
     spans: TIntoIterator,
+    interval: Option<chrono::Duration>,
   ) -> Result<
     impl Stream<Item = Result<ReadResponse, SendError>> + Send + Sync,
     StreamError,
@@ -169,7 +222,9 @@ impl Worker {
       .send_async(TaskRequest::Read(ReadTaskRequest::new(
         destination,
         spans,
-        ReadRequestKind::Stream,
+        ReadRequestKind::Stream(interval.map(|interval| {
+          tokio::time::Duration::from_millis(interval.num_milliseconds() as u64)
+        })),
         sender,
       )))
       .await
@@ -211,13 +266,14 @@ type TaskHandle = tokio::task::JoinHandle<()>;
 #[derive(Clone, Debug)]
 enum ReadRequestKind {
   Read,
-  Stream,
+  // NOTE: streams without an interval are read as fast as possible
+  Stream(Option<tokio::time::Duration>),
 }
 
 #[derive(Clone, Debug)]
 struct ReadTaskRequest {
   destination: Destination,
-  spans: Vec<SimpleSpan>,
+  spans: Vec<Batch<SimpleSpan>>,
   kind: ReadRequestKind,
   sender: ReadResponseSender,
 }
@@ -237,7 +293,7 @@ enum TaskRequest {
 }
 
 impl ReadTaskRequest {
-  fn new<TSpan: Span, TIntoIterator: IntoIterator<Item = TSpan>>(
+  fn new<TIntoIterator: IntoIterator<Item = Batch<SimpleSpan>>>(
     destination: Destination,
     spans: TIntoIterator,
     kind: ReadRequestKind,
@@ -245,13 +301,7 @@ impl ReadTaskRequest {
   ) -> Self {
     Self {
       destination,
-      spans: spans
-        .into_iter()
-        .map(|span| SimpleSpan {
-          address: span.address(),
-          quantity: span.quantity(),
-        })
-        .collect::<Vec<_>>(),
+      spans: spans.into_iter().collect::<Vec<_>>(),
       kind,
       sender,
     }
@@ -311,14 +361,38 @@ struct ReadRequestStorage {
   id: Id,
   sender: ReadResponseSender,
   destination: Destination,
-  spans: Vec<SimpleSpan>,
+  spans: Vec<Batch<SimpleSpan>>,
   partial: ReadPartial,
   generation: u64,
+  interval: Option<tokio::time::Duration>,
+  due: tokio::time::Instant,
+}
+
+impl ReadRequestStorage {
+  fn is_due(&self, now: tokio::time::Instant) -> bool {
+    self.due <= now
+  }
+
+  // NOTE: skips missed samples instead of bursting to catch up
+  fn reschedule(&mut self) {
+    if let Some(interval) = self.interval {
+      let now = tokio::time::Instant::now();
+      self.due = match self.due.checked_add(interval) {
+        Some(due) if due > now => due,
+        _ => now,
+      };
+    }
+  }
 }
 
+// NOTE: batch span -> spans that cover the batch without illegal addresses
+// and spans missing from the pieces are illegal by themselves
+type Splits = HashMap<SimpleSpan, Vec<SimpleSpan>>;
+
 #[derive(Debug)]
 struct Task {
   connections: HashMap<Device, Connection>,
+  splits: HashMap<Destination, Splits>,
   receiver: RequestReceiver,
   reads: Vec<ReadRequestStorage>,
   writes: Vec<WriteRequestStorage>,
@@ -327,6 +401,8 @@ struct Task {
   timeout: chrono::Duration,
   congestion_backoff: tokio::time::Duration,
   partial_retries: u32,
+  bus: BusConfig,
+  desyncs: Arc<AtomicU64>,
 }
 
 impl Task {
@@ -335,9 +411,12 @@ impl Task {
     receiver: RequestReceiver,
     congestion_backoff: chrono::Duration,
     partial_retries: u32,
+    bus: BusConfig,
+    desyncs: Arc<AtomicU64>,
   ) -> Self {
     Self {
       connections: HashMap::new(),
+      splits: HashMap::new(),
       receiver,
       writes: Vec::new(),
       reads: Vec::new(),
@@ -348,16 +427,27 @@ impl Task {
         congestion_backoff.num_milliseconds() as u64,
       ),
       partial_retries,
+      bus,
+      desyncs,
     }
   }
 
   pub(crate) async fn execute(mut self) {
     loop {
+      let now = tokio::time::Instant::now();
       if self.reads.is_empty()
-        && self.streams.is_empty()
         && self.writes.is_empty()
+        && !self.streams.iter().any(|stream| stream.is_due(now))
       {
-        if let Err(error) = self.recv_async_new_request().await {
+        let next_due = self.streams.iter().map(|stream| stream.due).min();
+        let received = match next_due {
+          Some(due) => tokio::select! {
+            received = self.recv_async_new_request() => Some(received),
+            () = tokio::time::sleep_until(due) => None,
+          },
+          None => Some(self.recv_async_new_request().await),
+        };
+        if let Some(Err(error)) = received {
           match error {
             flume::RecvError::Disconnected => return,
           }
@@ -382,10 +472,17 @@ impl Task {
         if !self.streams.is_empty() {
           self.streams = Vec::new();
         }
-      } else if let Some(generation) =
-        self.streams.iter().map(|stream| stream.generation).min()
-      {
-        self.process_streams(&mut metrics, generation).await;
+      } else {
+        let now = tokio::time::Instant::now();
+        if let Some(generation) = self
+          .streams
+          .iter()
+          .filter(|stream| stream.is_due(now))
+          .map(|stream| stream.generation)
+          .min()
+        {
+          self.process_streams(&mut metrics, generation, now).await;
+        }
       }
 
       if !self.terminate {
@@ -411,6 +508,7 @@ impl Task {
         &mut self.connections,
         &read.destination,
         Either::Left(&read.sender),
+        &self.bus,
       )
       .await
       {
@@ -425,19 +523,34 @@ impl Task {
         }
       };
 
-      match Self::read(
+      let result = Self::read(
         read,
         metrics,
         connection,
+        self.splits.entry(read.destination.clone()).or_default(),
         self.timeout,
         self.congestion_backoff,
       )
-      .await
-      {
-        Either::Left(partial) => {
+      .await;
+      metrics.add_desyncs(connection, &read.destination.device, &self.desyncs);
+
+      match result {
+        Err(error) => {
+          if let Err(error) = read.sender.try_send(Err(error)) {
+            // NOTE: error -> trace because this should fail when we already cancelled the future from caller
+            tracing::trace!(
+              "Failed sending read error to {:?} {}",
+              read.destination,
+              error,
+            )
+          }
+
+          reads_to_remove.push(read.id);
+        }
+        Ok(Either::Left(partial)) => {
           read.partial = partial;
         }
-        Either::Right(response) => {
+        Ok(Either::Right(response)) => {
           if let Err(error) = read.sender.try_send(Ok(response)) {
             // NOTE: error -> trace because this should fail when we already cancelled the future from caller
             tracing::trace!(
@@ -453,7 +566,7 @@ impl Task {
     }
     self
       .reads
-      .retain(|read| !reads_to_remove.iter().any(|id| *id == read.id));
+      .retain(|read| !reads_to_remove.contains(&read.id));
 
     tracing::trace!(
       "Removed reads {:?} - retained {:?}",
@@ -483,6 +596,7 @@ impl Task {
         &mut self.connections,
         &write.destination,
         Either::Right(&write.sender),
+        &self.bus,
       )
       .await
       {
@@ -497,19 +611,33 @@ impl Task {
         }
       };
 
-      match Self::write(
+      let result = Self::write(
         write,
         metrics,
         connection,
         self.timeout,
         self.congestion_backoff,
       )
-      .await
-      {
-        Either::Left(partial) => {
+      .await;
+      metrics.add_desyncs(connection, &write.destination.device, &self.desyncs);
+
+      match result {
+        Err(error) => {
+          if let Err(error) = write.sender.try_send(Err(error)) {
+            // NOTE: error -> trace because this should fail when we already cancelled the future from caller
+            tracing::trace!(
+              "Failed sending write error to {:?} {}",
+              write.destination,
+              error,
+            )
+          }
+
+          writes_to_remove.push(write.id);
+        }
+        Ok(Either::Left(partial)) => {
           write.partial = partial;
         }
-        Either::Right(response) => {
+        Ok(Either::Right(response)) => {
           if let Err(error) = write.sender.try_send(Ok(response)) {
             // NOTE: error -> trace because this should fail when we already cancelled the future from caller
             tracing::trace!(
@@ -525,7 +653,7 @@ impl Task {
     }
     self
       .writes
-      .retain(|write| !writes_to_remove.iter().any(|id| *id == write.id));
+      .retain(|write| !writes_to_remove.contains(&write.id));
 
     tracing::trace!(
       "Removed writes {:?} - retained {:?}",
@@ -538,7 +666,12 @@ impl Task {
     );
   }
 
-  async fn process_streams(&mut self, metrics: &mut Metrics, generation: u64) {
+  async fn process_streams(
+    &mut self,
+    metrics: &mut Metrics,
+    generation: u64,
+    now: tokio::time::Instant,
+  ) {
     let mut streams_to_remove = Vec::new();
     for index in 0..self.streams.len() {
       let stream = self.streams.index_mut(index);
@@ -551,7 +684,7 @@ impl Task {
         continue;
       }
 
-      if stream.generation != generation {
+      if stream.generation != generation || !stream.is_due(now) {
         continue;
       }
 
@@ -561,6 +694,7 @@ impl Task {
           spans: vec![None; stream.spans.len()],
           retries: 0,
         };
+        stream.reschedule();
         continue;
       }
 
@@ -568,6 +702,7 @@ impl Task {
         &mut self.connections,
         &stream.destination,
         Either::Left(&stream.sender),
+        &self.bus,
       )
       .await
       {
@@ -582,44 +717,56 @@ impl Task {
         }
       };
 
-      match Self::read(
+      let result = Self::read(
         stream,
         metrics,
         connection,
+        self.splits.entry(stream.destination.clone()).or_default(),
         self.timeout,
         self.congestion_backoff,
       )
-      .await
-      {
-        Either::Left(partial) => {
+      .await;
+      metrics.add_desyncs(
+        connection,
+        &stream.destination.device,
+        &self.desyncs,
+      );
+
+      let response = match result {
+        Err(error) => Some(Err(error)),
+        Ok(Either::Left(partial)) => {
           stream.partial = partial;
+          None
         }
-        Either::Right(response) => {
-          match stream.sender.try_send(Ok(response)) {
-            Ok(()) => {
-              stream.partial = ReadPartial {
-                spans: vec![None; stream.spans.len()],
-                retries: 0,
-              };
-              stream.generation = stream.generation.saturating_add(1);
-            }
-            Err(error) => {
-              // NOTE: error -> trace because this should fail when we already cancelled the future from caller
-              tracing::trace!(
-                "Failed sending stream response to {:?} {}",
-                stream.destination,
-                error,
-              );
-
-              streams_to_remove.push(stream.id);
-            }
+        Ok(Either::Right(response)) => Some(Ok(response)),
+      };
+
+      if let Some(response) = response {
+        match stream.sender.try_send(response) {
+          Ok(()) => {
+            stream.partial = ReadPartial {
+              spans: vec![None; stream.spans.len()],
+              retries: 0,
+            };
+            stream.generation = stream.generation.saturating_add(1);
+            stream.reschedule();
+          }
+          Err(error) => {
+            // NOTE: error -> trace because this should fail when we already cancelled the future from caller
+            tracing::trace!(
+              "Failed sending stream response to {:?} {}",
+              stream.destination,
+              error,
+            );
+
+            streams_to_remove.push(stream.id);
           }
         }
-      };
+      }
     }
     self
       .streams
-      .retain(|stream| !streams_to_remove.iter().any(|id| *id == stream.id));
+      .retain(|stream| !streams_to_remove.contains(&stream.id));
 
     tracing::trace!(
       "Removed streams {:?} - retained {:?}",
@@ -717,6 +864,11 @@ impl Task {
         .map(|stream| stream.generation)
         .max()
         .unwrap_or(0),
+      interval: match kind {
+        ReadRequestKind::Read => None,
+        ReadRequestKind::Stream(interval) => interval,
+      },
+      due: tokio::time::Instant::now(),
     };
 
     match kind {
@@ -729,7 +881,7 @@ impl Task {
           .unwrap_or_else(|i| i);
         self.reads.insert(index, storage);
       }
-      ReadRequestKind::Stream => {
+      ReadRequestKind::Stream(_) => {
         let index = self
           .streams
           .binary_search_by(|s| {
@@ -787,6 +939,7 @@ impl Task {
     connections: &'a mut HashMap<Device, Connection>,
     destination: &Destination,
     sender: Either<&ReadResponseSender, &WriteResponseSender>,
+    bus: &BusConfig,
   ) -> ConnectionAttempt<'a> {
     match connections.get_mut(&destination.device) {
       Some(connection) => {
@@ -795,7 +948,7 @@ impl Task {
         ConnectionAttempt::Existing(connection)
       }
       None => {
-        let mut connection = Connection::new(destination.device.clone());
+        let mut connection = Connection::new(destination.device.clone(), bus);
         match connection.ensure_connected(destination.slave).await {
           Ok(()) => {
             tracing::trace!("Connected to new connection");
@@ -840,17 +993,20 @@ impl Task {
     storage: &ReadRequestStorage,
     metrics: &mut Metrics,
     connection: &mut Connection,
+    splits: &mut Splits,
     timeout: chrono::Duration,
     congestion_backoff: tokio::time::Duration,
-  ) -> Either<ReadPartial, ReadResponse> {
+  ) -> Result<Either<ReadPartial, ReadResponse>, SendError> {
     let partial = {
       let mut data = Vec::new();
-      for (span, partial) in storage
-        .spans
-        .iter()
-        .cloned()
-        .zip(storage.partial.spans.iter())
+      for (batch, partial) in
+        storage.spans.iter().zip(storage.partial.spans.iter())
       {
+        let span = SimpleSpan {
+          address: batch.address,
+          quantity: batch.quantity,
+          function: batch.function,
+        };
         let read = match partial {
           Some(partial) => Some(partial.clone()),
           None => {
@@ -874,13 +1030,18 @@ impl Task {
               None
             } else {
               let start = chrono::Utc::now();
-              let data = (*connection)
-                .read(storage.destination.slave, span, timeout)
-                .await;
+              let data = Self::read_batch(
+                connection,
+                storage.destination.slave,
+                batch,
+                splits,
+                timeout,
+              )
+              .await;
               let end = chrono::Utc::now();
 
               match data {
-                Ok(data) => {
+                Ok((data, missing)) => {
                   metrics
                     .reads
                     .entry(storage.destination.clone())
@@ -895,6 +1056,7 @@ impl Task {
                   Some(ReadResponseEntry {
                     inner: data,
                     timestamp: chrono::Utc::now(),
+                    missing,
                   })
                 }
                 Err(error) => {
@@ -912,10 +1074,20 @@ impl Task {
                       time: Some(end.signed_duration_since(start)),
                     });
 
-                  if let ReadError::Read(io_error) = error {
-                    if io_error.kind() == std::io::ErrorKind::InvalidData {
+                  match error {
+                    ReadError::Exception(exception)
+                      if !exception.is_transient()
+                        && exception.code
+                          != ExceptionCode::IllegalDataAddress =>
+                    {
+                      return Err(SendError::Exception(exception));
+                    }
+                    ReadError::Read(io_error)
+                      if io_error.kind() == std::io::ErrorKind::InvalidData =>
+                    {
                       tokio::time::sleep(congestion_backoff).await;
                     }
+                    _ => {}
                   };
 
                   None
@@ -933,16 +1105,102 @@ impl Task {
 
     if partial.iter().all(|x| x.is_some()) {
       tracing::trace!("Fully read");
-      Either::Right(partial.iter().flatten().cloned().collect::<Vec<_>>())
+      Ok(Either::Right(
+        partial.iter().flatten().cloned().collect::<Vec<_>>(),
+      ))
     } else {
       tracing::trace!("Partially read");
-      Either::Left(ReadPartial {
+      Ok(Either::Left(ReadPartial {
         spans: partial,
         retries: storage.partial.retries.saturating_add(1),
-      })
+      }))
+    }
+  }
+
+  async fn read_batch(
+    connection: &mut Connection,
+    slave: Option<u8>,
+    batch: &Batch<SimpleSpan>,
+    splits: &mut Splits,
+    timeout: chrono::Duration,
+  ) -> Result<(super::connection::ReadResponse, Vec<SimpleSpan>), ReadError> {
+    let span = SimpleSpan {
+      address: batch.address,
+      quantity: batch.quantity,
+      function: batch.function,
+    };
+
+    if let Some(pieces) = splits.get(&span).cloned() {
+      match Self::read_pieces(connection, slave, &pieces, timeout).await {
+        Ok(data) => return stitch_pieces(span, batch, data),
+        Err(error) if is_illegal_data_address(&error) => {
+          tracing::debug!("Split of batch {:?} became illegal", span);
+          splits.remove(&span);
+        }
+        Err(error) => return Err(error),
+      }
+    }
+
+    match connection.read(slave, span, timeout).await {
+      Ok(data) => Ok((data, Vec::new())),
+      Err(error) if is_illegal_data_address(&error) => {
+        let data = Self::split_batch(connection, slave, batch, timeout).await?;
+        let pieces = data.iter().map(|(piece, _)| *piece).collect::<Vec<_>>();
+        tracing::debug!("Split batch {:?} into {:?}", span, pieces);
+        splits.insert(span, pieces);
+
+        stitch_pieces(span, batch, data)
+      }
+      Err(error) => Err(error),
     }
   }
 
+  async fn read_pieces(
+    connection: &mut Connection,
+    slave: Option<u8>,
+    pieces: &[SimpleSpan],
+    timeout: chrono::Duration,
+  ) -> Result<Vec<(SimpleSpan, super::connection::ReadResponse)>, ReadError> {
+    let mut data = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+      data.push((*piece, connection.read(slave, *piece, timeout).await?));
+    }
+
+    Ok(data)
+  }
+
+  // NOTE: groups of a single span that are still illegal are left out
+  async fn split_batch(
+    connection: &mut Connection,
+    slave: Option<u8>,
+    batch: &Batch<SimpleSpan>,
+    timeout: chrono::Duration,
+  ) -> Result<Vec<(SimpleSpan, super::connection::ReadResponse)>, ReadError> {
+    let mut pieces = Vec::new();
+    let mut pending = vec![batch.inner.as_slice()];
+    while let Some(group) = pending.pop() {
+      let span = match cover_spans(group) {
+        Some(span) => span,
+        None => continue,
+      };
+
+      match connection.read(slave, span, timeout).await {
+        Ok(data) => pieces.push((span, data)),
+        Err(error) if is_illegal_data_address(&error) && group.len() > 1 => {
+          let (left, right) = group.split_at(group.len() / 2);
+          pending.push(right);
+          pending.push(left);
+        }
+        Err(error) if is_illegal_data_address(&error) => {
+          tracing::warn!("Span {:?} has an illegal address", span);
+        }
+        Err(error) => return Err(error),
+      }
+    }
+
+    Ok(pieces)
+  }
+
   #[tracing::instrument(skip_all, fields(address = ?storage.destination))]
   async fn write(
     storage: &WriteRequestStorage,
@@ -950,7 +1208,7 @@ impl Task {
     connection: &mut Connection,
     timeout: chrono::Duration,
     congestion_backoff: tokio::time::Duration,
-  ) -> Either<WritePartial, WriteResponse> {
+  ) -> Result<Either<WritePartial, WriteResponse>, SendError> {
     let partial = {
       let mut data = Vec::new();
       for (record, partial) in storage
@@ -1020,10 +1278,18 @@ impl Task {
                       time: Some(end.signed_duration_since(start)),
                     });
 
-                  if let WriteError::Read(io_error) = error {
-                    if io_error.kind() == std::io::ErrorKind::InvalidData {
+                  match error {
+                    WriteError::Exception(exception)
+                      if !exception.is_transient() =>
+                    {
+                      return Err(SendError::Exception(exception));
+                    }
+                    WriteError::Read(io_error)
+                      if io_error.kind() == std::io::ErrorKind::InvalidData =>
+                    {
                       tokio::time::sleep(congestion_backoff).await;
                     }
+                    _ => {}
                   };
 
                   None
@@ -1041,15 +1307,84 @@ impl Task {
 
     if partial.iter().all(|x| x.is_some()) {
       tracing::trace!("Fully read");
-      Either::Right(partial.iter().flatten().cloned().collect::<Vec<_>>())
+      Ok(Either::Right(
+        partial.iter().flatten().cloned().collect::<Vec<_>>(),
+      ))
     } else {
       tracing::trace!("Partially read");
-      Either::Left(WritePartial {
+      Ok(Either::Left(WritePartial {
         records: partial,
         retries: storage.partial.retries.saturating_add(1),
-      })
+      }))
+    }
+  }
+}
+
+fn is_illegal_data_address(error: &ReadError) -> bool {
+  matches!(
+    error,
+    ReadError::Exception(Exception {
+      code: ExceptionCode::IllegalDataAddress,
+      ..
+    })
+  )
+}
+
+fn cover_spans(spans: &[SimpleSpan]) -> Option<SimpleSpan> {
+  let first = spans.first()?;
+  let last = spans.last()?;
+  let end = last.address.checked_add(last.quantity)?;
+
+  Some(SimpleSpan {
+    address: first.address,
+    quantity: end.checked_sub(first.address)?,
+    function: first.function,
+  })
+}
+
+// NOTE: registers between pieces are left as zeros and the spans they
+// hold are reported as missing so they never get parsed
+fn stitch_pieces(
+  span: SimpleSpan,
+  batch: &Batch<SimpleSpan>,
+  pieces: Vec<(SimpleSpan, super::connection::ReadResponse)>,
+) -> Result<(super::connection::ReadResponse, Vec<SimpleSpan>), ReadError> {
+  let missing = batch
+    .inner
+    .iter()
+    .filter(|inner| !pieces.iter().any(|(piece, _)| covers(piece, inner)))
+    .copied()
+    .collect::<Vec<_>>();
+
+  let mut data = vec![0u16; span.quantity.into()];
+  for (piece, piece_data) in pieces {
+    let start = piece.address.checked_sub(span.address).map(usize::from);
+    let end = start.and_then(|start| start.checked_add(piece_data.len()));
+    match (start, end) {
+      (Some(start), Some(end)) => match data.get_mut(start..end) {
+        Some(slice) => slice.copy_from_slice(&piece_data),
+        None => return Err(invalid_piece(span, piece)),
+      },
+      _ => return Err(invalid_piece(span, piece)),
     }
   }
+
+  Ok((data, missing))
+}
+
+fn covers(piece: &SimpleSpan, span: &SimpleSpan) -> bool {
+  let piece_end =
+    u32::from(piece.address).saturating_add(piece.quantity.into());
+  let span_end = u32::from(span.address).saturating_add(span.quantity.into());
+
+  piece.address <= span.address && span_end <= piece_end
+}
+
+fn invalid_piece(span: SimpleSpan, piece: SimpleSpan) -> ReadError {
+  ReadError::Read(std::io::Error::new(
+    std::io::ErrorKind::InvalidData,
+    format!("Piece {piece:?} is outside of batch {span:?}"),
+  ))
 }
 
 #[derive(Debug, Clone)]
@@ -1074,6 +1409,7 @@ struct ReadMetric {
 struct Metrics {
   reads: HashMap<Destination, Vec<ReadMetric>>,
   writes: HashMap<Destination, Vec<WriteMetric>>,
+  desyncs: HashMap<Device, u64>,
 }
 
 impl Metrics {
@@ -1081,6 +1417,72 @@ impl Metrics {
     Self {
       reads: HashMap::new(),
       writes: HashMap::new(),
+      desyncs: HashMap::new(),
     }
   }
+
+  fn add_desyncs(
+    &mut self,
+    connection: &mut Connection,
+    device: &Device,
+    total: &AtomicU64,
+  ) {
+    let desyncs = connection.take_desyncs();
+    if desyncs > 0 {
+      total.fetch_add(desyncs, Ordering::Relaxed);
+      let count = self.desyncs.entry(device.clone()).or_default();
+      *count = count.saturating_add(desyncs);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::service::modbus::span::Function;
+  use tokio_modbus::{Address, Quantity};
+
+  fn span(address: Address, quantity: Quantity) -> SimpleSpan {
+    SimpleSpan {
+      address,
+      quantity,
+      function: Function::HoldingRegisters,
+    }
+  }
+
+  #[test]
+  fn stitched_gaps_are_zero_filled_and_missing() {
+    let inner = vec![span(10, 2), span(12, 2), span(14, 2), span(16, 2)];
+    let batch = Batch::<SimpleSpan> {
+      address: 10,
+      quantity: 8,
+      function: Function::HoldingRegisters,
+      inner,
+    };
+    let whole = span(10, 8);
+    let pieces =
+      vec![(span(10, 4), vec![1, 2, 3, 4]), (span(16, 2), vec![7, 8])];
+
+    assert!(covers(&span(10, 4), &span(12, 2)));
+    assert!(!covers(&span(10, 4), &span(13, 2)));
+    assert_eq!(cover_spans(&batch.inner), Some(whole));
+
+    #[allow(clippy::unwrap_used, reason = "pieces are inside the batch")]
+    let (data, missing) = stitch_pieces(whole, &batch, pieces).unwrap();
+    assert_eq!(data, vec![1, 2, 3, 4, 0, 0, 7, 8]);
+    assert_eq!(missing, vec![span(14, 2)]);
+  }
+
+  #[test]
+  fn pieces_outside_the_batch_are_rejected() {
+    let batch = Batch::<SimpleSpan> {
+      address: 10,
+      quantity: 2,
+      function: Function::HoldingRegisters,
+      inner: vec![span(10, 2)],
+    };
+    let pieces = vec![(span(11, 2), vec![1, 2])];
+
+    assert!(stitch_pieces(span(10, 2), &batch, pieces).is_err());
+  }
 }
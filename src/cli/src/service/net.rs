@@ -1,8 +1,9 @@
-use std::net::{IpAddr, SocketAddr};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
 
+use futures::StreamExt;
 use ipnet::{IpAddrRange, IpNet};
 use tokio::net::TcpStream;
-use tokio::task::JoinHandle;
 
 use crate::*;
 
@@ -11,6 +12,13 @@ pub(crate) struct Service {
   ip_range: IpAddrRange,
   timeout: std::time::Duration,
   modbus_port: u16,
+  rtu_over_tcp_port: Option<u16>,
+  udp_port: Option<u16>,
+  concurrency: usize,
+  interfaces: config::NetworkInterfaces,
+  ports: Vec<u16>,
+  ipv6: bool,
+  exclude: Vec<IpNet>,
 }
 
 impl service::Service for Service {
@@ -21,6 +29,13 @@ impl service::Service for Service {
         config.network.timeout.num_milliseconds() as u64,
       ),
       modbus_port: config.network.modbus_port,
+      rtu_over_tcp_port: config.network.rtu_over_tcp_port,
+      udp_port: config.network.udp_port,
+      concurrency: config.network.concurrency,
+      interfaces: config.network.interfaces,
+      ports: config.network.ports,
+      ipv6: config.network.ipv6,
+      exclude: config.network.exclude,
     }
   }
 }
@@ -28,47 +43,167 @@ impl service::Service for Service {
 impl Service {
   #[tracing::instrument(skip(self))]
   pub(crate) async fn scan_modbus(&self) -> Vec<SocketAddr> {
-    let default_interface_ranges =
-      match netdev::interface::get_default_interface() {
-        Ok(interface) => interface
-          .ipv4
-          .iter()
-          .map(|addr| IpNet::V4(*addr).hosts())
-          .collect::<Vec<_>>(),
-        Err(_) => Vec::new(),
-      };
+    self.scan_tcp(&self.ports).await
+  }
+
+  #[tracing::instrument(skip(self))]
+  pub(crate) async fn scan_modbus_rtu_over_tcp(&self) -> Vec<SocketAddr> {
+    match self.rtu_over_tcp_port {
+      Some(port) => self.scan_tcp(&[port]).await,
+      None => Vec::new(),
+    }
+  }
+
+  // NOTE: udp is connectionless so there is nothing to probe here and
+  // discovery has to find out by reading from every configured address
+  #[tracing::instrument(skip(self))]
+  pub(crate) async fn scan_modbus_udp(&self) -> Vec<SocketAddr> {
+    match self.udp_port {
+      Some(port) => self
+        .ip_range
+        .into_iter()
+        .filter(|ip| !self.is_excluded(ip))
+        .map(|ip| SocketAddr::new(ip, port))
+        .collect::<Vec<_>>(),
+      None => Vec::new(),
+    }
+  }
+
+  // NOTE: connections are bounded so that big subnets don't run out of
+  // file descriptors
+  async fn scan_tcp(&self, ports: &[u16]) -> Vec<SocketAddr> {
+    let candidates = self.candidates().await;
+    tracing::trace!(
+      "Matching {:?} candidates on ports {:?}",
+      candidates.len(),
+      ports
+    );
+
+    let socket_addresses = candidates
+      .into_iter()
+      .flat_map(|candidate| {
+        ports.iter().map(move |port| {
+          let mut socket_address = candidate;
+          socket_address.set_port(*port);
+          socket_address
+        })
+      })
+      .collect::<Vec<_>>();
 
     let timeout = self.timeout;
-    let mut matched_ips = Vec::new();
-    let ip_scans = self
+    let matched_addresses =
+      futures::stream::iter(socket_addresses.into_iter().map(
+        |socket_address| async move {
+          let connection =
+            tokio::time::timeout(timeout, TcpStream::connect(&socket_address))
+              .await;
+          matches!(connection, Ok(Ok(_))).then_some(socket_address)
+        },
+      ))
+      .buffer_unordered(self.concurrency)
+      .filter_map(|socket_address| async move { socket_address })
+      .collect::<Vec<_>>()
+      .await;
+    tracing::trace!("Found {:?} addresses", matched_addresses.len());
+
+    matched_addresses
+  }
+
+  // NOTE: ports get filled in by the scan
+  async fn candidates(&self) -> Vec<SocketAddr> {
+    let interfaces = self.interfaces();
+    let interface_ranges = interfaces
+      .iter()
+      .flat_map(|interface| interface.ipv4.iter())
+      .map(|addr| IpNet::V4(*addr).hosts())
+      .collect::<Vec<_>>();
+    let neighbours = if self.ipv6 {
+      self.ipv6_neighbours(&interfaces).await
+    } else {
+      Vec::new()
+    };
+
+    let mut seen = HashSet::new();
+    self
       .ip_range
       .into_iter()
-      .chain(default_interface_ranges.into_iter().flatten())
-      .map(|ip| {
-        let socket_address = self.to_socket(ip);
-        (
-          socket_address,
-          tokio::spawn(async move {
-            let timeout = tokio::time::timeout(
-              timeout,
-              TcpStream::connect(&socket_address),
-            )
-            .await;
-            matches!(timeout, Ok(Ok(_)))
-          }),
-        )
-      })
-      .collect::<Vec<(SocketAddr, JoinHandle<bool>)>>();
+      .chain(interface_ranges.into_iter().flatten())
+      .map(|ip| SocketAddr::new(ip, 0))
+      .chain(neighbours)
+      .filter(|socket_address| !self.is_excluded(&socket_address.ip()))
+      .filter(|socket_address| seen.insert(*socket_address))
+      .collect()
+  }
 
-    tracing::trace!("Matching {:?}", self.ip_range);
-    for (ip, scan) in ip_scans {
-      if let Ok(true) = scan.await {
-        matched_ips.push(ip)
+  fn interfaces(&self) -> Vec<netdev::Interface> {
+    match &self.interfaces {
+      config::NetworkInterfaces::Default => {
+        netdev::interface::get_default_interface()
+          .into_iter()
+          .collect()
+      }
+      config::NetworkInterfaces::All => netdev::interface::get_interfaces()
+        .into_iter()
+        .filter(|interface| interface.is_up() && !interface.is_loopback())
+        .collect(),
+      config::NetworkInterfaces::Selected(names) => {
+        let interfaces = netdev::interface::get_interfaces()
+          .into_iter()
+          .filter(|interface| names.contains(&interface.name))
+          .collect::<Vec<_>>();
+        for name in names {
+          if !interfaces.iter().any(|interface| &interface.name == name) {
+            tracing::warn!("Network interface {} not found", name);
+          }
+        }
+        interfaces
       }
     }
-    tracing::trace!("Found {:?} ips", matched_ips.len());
+  }
+
+  // NOTE: ipv6 subnets are way too big to scan so we only try hosts
+  // the kernel already knows about and link local ones need the scope
+  async fn ipv6_neighbours(
+    &self,
+    interfaces: &[netdev::Interface],
+  ) -> Vec<SocketAddr> {
+    let output = match tokio::process::Command::new("ip")
+      .args(["-6", "neigh", "show"])
+      .output()
+      .await
+    {
+      Ok(output) => output,
+      Err(error) => {
+        tracing::warn!("Failed listing ipv6 neighbours {}", error);
+        return Vec::new();
+      }
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .filter_map(|line| {
+        let mut parts = line.split_whitespace();
+        let ip = parts.next()?.parse::<Ipv6Addr>().ok()?;
+        let device = parts.clone().skip_while(|part| *part != "dev").nth(1)?;
+        if parts.any(|part| part == "FAILED" || part == "INCOMPLETE") {
+          return None;
+        }
+        let interface = interfaces
+          .iter()
+          .find(|interface| interface.name == device)?;
+        let scope = if ip.is_unicast_link_local() {
+          interface.index
+        } else {
+          0
+        };
+
+        Some(SocketAddr::V6(SocketAddrV6::new(ip, 0, 0, scope)))
+      })
+      .collect()
+  }
 
-    matched_ips
+  fn is_excluded(&self, ip: &IpAddr) -> bool {
+    self.exclude.iter().any(|net| net.contains(ip))
   }
 
   pub(crate) fn to_socket(&self, ip: IpAddr) -> SocketAddr {
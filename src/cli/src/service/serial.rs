@@ -1,20 +1,34 @@
+use std::collections::HashMap;
+
 use crate::*;
 
-// TODO: detect parameters
+use super::modbus::connection::Line;
 
 #[derive(Debug, Clone)]
-pub(crate) struct Service {}
+pub(crate) struct Service {
+  line: Line,
+  ports: HashMap<String, Line>,
+  detect: bool,
+  candidates: Vec<Line>,
+}
 
 impl service::Service for Service {
-  fn new(_config: config::Values) -> Self {
-    Self {}
+  fn new(config: config::Values) -> Self {
+    Self {
+      line: config.serial.line,
+      ports: config.serial.ports,
+      detect: config.serial.detect,
+      candidates: config.serial.candidates,
+    }
   }
 }
 
+// NOTE: lines are in the order they should be tried in
+// and the configured line always goes first
 #[derive(Debug, Clone)]
 pub(crate) struct SerialPort {
   pub(crate) path: String,
-  pub(crate) baud_rate: u32,
+  pub(crate) lines: Vec<Line>,
 }
 
 impl Service {
@@ -26,7 +40,7 @@ impl Service {
     };
 
     tracing::trace!("Matching {:?}", available);
-    let matched = available
+    let mut matched = available
       .into_iter()
       .filter(|port| {
         port.port_type == serialport::SerialPortType::Unknown
@@ -34,14 +48,45 @@ impl Service {
       })
       .filter(|port| FILE_PATH_REGEX.is_match(&port.port_name))
       .map(|port| SerialPort {
+        lines: self.lines_for(&port.port_name),
         path: port.port_name,
-        baud_rate: 38400,
       })
       .collect::<Vec<_>>();
+
+    // NOTE: pseudo terminals like the simulator ones are not enumerated
+    // so explicitly configured ports are scanned whenever they exist
+    let mut configured = self
+      .ports
+      .keys()
+      .filter(|path| !matched.iter().any(|port| &port.path == *path))
+      .filter(|path| std::path::Path::new(path).exists())
+      .map(|path| SerialPort {
+        lines: self.lines_for(path),
+        path: path.clone(),
+      })
+      .collect::<Vec<_>>();
+    configured.sort_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+    matched.extend(configured);
     tracing::trace!("Matched {:?}", matched);
 
     matched
   }
+
+  fn lines_for(&self, path: &str) -> Vec<Line> {
+    let line = self.ports.get(path).copied().unwrap_or(self.line);
+    if !self.detect {
+      return vec![line];
+    }
+
+    let mut lines = vec![line];
+    for candidate in &self.candidates {
+      if !lines.contains(candidate) {
+        lines.push(*candidate);
+      }
+    }
+
+    lines
+  }
 }
 
 lazy_static::lazy_static! {
@@ -0,0 +1,552 @@
+use std::{
+  collections::HashMap, iter::Peekable, str::Chars, sync::Mutex, time::Instant,
+};
+
+use either::Either;
+use rand::Rng;
+use rust_decimal::Decimal;
+use tokio_modbus::{Address, Quantity, Request, Response};
+
+use crate::config;
+use crate::service::modbus::{
+  self,
+  batch::max_quantity_for,
+  connection::ExceptionCode,
+  identification,
+  server::{exception, function_code, words_to_bits},
+  span::Function,
+  RegisterStorage,
+};
+
+// NOTE: values are sampled from the time elapsed since startup on every read
+// so ramps and counters move without any background task
+
+#[derive(Debug)]
+pub(crate) struct Device {
+  kind: String,
+  slave: u8,
+  registers: Vec<Register>,
+  written: Mutex<HashMap<Address, u16>>,
+  faults: config::SimulatorFaults,
+  started: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct Register {
+  function: Function,
+  address: Address,
+  kind: modbus::RegisterKindStorage,
+  value: config::SimulatorValue,
+}
+
+#[derive(Debug, Clone)]
+enum Sample {
+  Number(Decimal),
+  Text(String),
+}
+
+impl Device {
+  pub(crate) fn new(
+    device: &config::Device,
+    simulated: config::SimulatorDevice,
+  ) -> Self {
+    let mut registers = Vec::new();
+
+    for (index, register) in device.detect.iter().enumerate() {
+      let value = match simulated
+        .detect
+        .as_ref()
+        .and_then(|detect| detect.get(index))
+      {
+        Some(value) => value.clone(),
+        None => match &register.r#match {
+          Either::Left(string) => string.clone(),
+          Either::Right(regex) => {
+            let sample = sample_match(regex.as_str());
+            if !regex.is_match(sample.as_str()) {
+              tracing::warn!(
+                "Failed sampling detect match {} of {} so set it explicitly",
+                regex.as_str(),
+                device.kind
+              );
+            }
+            sample
+          }
+        },
+      };
+      registers.push(Register {
+        function: register.function,
+        address: register.address,
+        kind: register.storage.clone(),
+        value: config::SimulatorValue::Text(value),
+      });
+    }
+
+    for register in &device.id {
+      registers.push(Register {
+        function: register.function,
+        address: register.address,
+        kind: register.storage.clone(),
+        value: config::SimulatorValue::Constant(Decimal::from(simulated.id)),
+      });
+    }
+
+    for register in device.measurement.iter().chain(
+      device
+        .groups
+        .iter()
+        .flat_map(|group| group.measurement.iter()),
+    ) {
+      registers.push(Register {
+        function: register.function,
+        address: register.address,
+        kind: register.storage.clone(),
+        value: simulated
+          .values
+          .get(&register.name)
+          .cloned()
+          .unwrap_or_else(|| default_value(register)),
+      });
+    }
+
+    Self {
+      kind: device.kind.clone(),
+      slave: simulated.slave,
+      registers,
+      written: Mutex::new(HashMap::new()),
+      faults: simulated.faults,
+      started: Instant::now(),
+    }
+  }
+
+  pub(crate) fn slave(&self) -> u8 {
+    self.slave
+  }
+
+  pub(crate) fn answers_wrong_slave(&self) -> bool {
+    roll(self.faults.wrong_slave)
+  }
+
+  #[tracing::instrument(skip(self), fields(kind = self.kind, slave = self.slave))]
+  pub(crate) fn respond(&self, request: Request<'_>) -> Option<Response> {
+    let function = function_code(&request);
+
+    if roll(self.faults.timeout) {
+      tracing::debug!("Injecting timeout");
+      return None;
+    }
+    if roll(self.faults.exception) {
+      tracing::debug!("Injecting exception");
+      return Some(exception(function, ExceptionCode::ServerDeviceBusy));
+    }
+
+    let response = match request {
+      Request::ReadHoldingRegisters(address, quantity) => self
+        .read(Function::HoldingRegisters, address, quantity)
+        .map(Response::ReadHoldingRegisters),
+      Request::ReadInputRegisters(address, quantity) => self
+        .read(Function::InputRegisters, address, quantity)
+        .map(Response::ReadInputRegisters),
+      Request::ReadCoils(address, quantity) => self
+        .read(Function::Coils, address, quantity)
+        .map(|words| Response::ReadCoils(words_to_bits(words))),
+      Request::ReadDiscreteInputs(address, quantity) => self
+        .read(Function::DiscreteInputs, address, quantity)
+        .map(|words| Response::ReadDiscreteInputs(words_to_bits(words))),
+      Request::Custom(identification::FUNCTION_CODE, data) => {
+        self.identify(&data)
+      }
+      Request::WriteSingleRegister(address, word) => {
+        self.write(address, &[word]);
+        Ok(Response::WriteSingleRegister(address, word))
+      }
+      Request::WriteMultipleRegisters(address, words) => {
+        self.write(address, &words);
+        Ok(Response::WriteMultipleRegisters(
+          address,
+          words.len() as Quantity,
+        ))
+      }
+      _ => Err(ExceptionCode::IllegalFunction),
+    };
+
+    Some(response.unwrap_or_else(|code| exception(function, code)))
+  }
+
+  // NOTE: broadcasts are never answered so only writes matter
+  pub(crate) fn broadcast(&self, request: &Request<'_>) {
+    match request {
+      Request::WriteSingleRegister(address, word) => {
+        self.write(*address, &[*word]);
+      }
+      Request::WriteMultipleRegisters(address, words) => {
+        self.write(*address, words);
+      }
+      _ => {}
+    }
+  }
+
+  fn read(
+    &self,
+    function: Function,
+    address: Address,
+    quantity: Quantity,
+  ) -> Result<Vec<u16>, ExceptionCode> {
+    if quantity == 0 || quantity > max_quantity_for(function) {
+      return Err(ExceptionCode::IllegalDataValue);
+    }
+
+    let start = u32::from(address);
+    let end = start.saturating_add(u32::from(quantity));
+    let mut words = vec![None; usize::from(quantity)];
+
+    for register in self
+      .registers
+      .iter()
+      .filter(|register| register.function == function)
+    {
+      let register_start = u32::from(register.address);
+      let register_end =
+        register_start.saturating_add(u32::from(register.kind.quantity()));
+      if register_end <= start || register_start >= end {
+        continue;
+      }
+
+      let sample = self.sample(&register.value);
+      for (at, value) in (register_start..register_end).zip(match sample {
+        Sample::Number(number) => register.kind.encode_number(number),
+        Sample::Text(text) => register.kind.encode_text(text.as_str()),
+      }) {
+        let word = at
+          .checked_sub(start)
+          .and_then(|offset| usize::try_from(offset).ok())
+          .and_then(|offset| words.get_mut(offset));
+        if let Some(word) = word {
+          *word = Some(value);
+        }
+      }
+    }
+
+    if function == Function::HoldingRegisters {
+      if let Ok(written) = self.written.lock() {
+        for (at, word) in (start..end).zip(words.iter_mut()) {
+          if word.is_none() {
+            *word = Address::try_from(at)
+              .ok()
+              .and_then(|at| written.get(&at).copied());
+          }
+        }
+      }
+    }
+
+    words
+      .into_iter()
+      .collect::<Option<Vec<_>>>()
+      .ok_or(ExceptionCode::IllegalDataAddress)
+  }
+
+  // NOTE: only specific object access is supported because that is
+  // the only way we ever read identification objects and objects are
+  // encoded on their own because their spans overlap
+  fn identify(&self, data: &[u8]) -> Result<Response, ExceptionCode> {
+    let &[identification::MEI_TYPE, identification::SPECIFIC_ACCESS, object] =
+      data
+    else {
+      return Err(ExceptionCode::IllegalDataValue);
+    };
+    let object = Address::from(object);
+    let register = self
+      .registers
+      .iter()
+      .find(|register| {
+        register.function == Function::DeviceIdentification
+          && register.address == object
+      })
+      .ok_or(ExceptionCode::IllegalDataAddress)?;
+    let bytes = match self.sample(&register.value) {
+      Sample::Number(number) => register.kind.encode_number(number),
+      Sample::Text(text) => register.kind.encode_text(text.as_str()),
+    }
+    .into_iter()
+    .flat_map(u16::to_be_bytes)
+    .collect::<Vec<_>>();
+
+    Ok(Response::Custom(
+      identification::FUNCTION_CODE,
+      identification::response(object, &bytes).into(),
+    ))
+  }
+
+  fn write(&self, address: Address, words: &[u16]) {
+    tracing::info!(
+      "Device {} on slave {} written {:?} at {}",
+      self.kind,
+      self.slave,
+      words,
+      address
+    );
+
+    if let Ok(mut written) = self.written.lock() {
+      for (at, word) in (address..=Address::MAX).zip(words.iter()) {
+        written.insert(at, *word);
+      }
+    }
+  }
+
+  fn sample(&self, value: &config::SimulatorValue) -> Sample {
+    let elapsed = self.started.elapsed();
+    let milliseconds = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+
+    match value {
+      config::SimulatorValue::Constant(value) => Sample::Number(*value),
+      config::SimulatorValue::Text(value) => Sample::Text(value.clone()),
+      config::SimulatorValue::Ramp { min, max, period } => {
+        let period =
+          u64::try_from(period.num_milliseconds()).unwrap_or(0).max(1);
+        let phase = milliseconds.checked_rem(period).unwrap_or(0);
+        let value = Decimal::from(phase)
+          .checked_div(Decimal::from(period))
+          .and_then(|fraction| max.checked_sub(*min)?.checked_mul(fraction))
+          .and_then(|offset| min.checked_add(offset))
+          .unwrap_or(*min);
+        Sample::Number(value)
+      }
+      config::SimulatorValue::Noise { mean, amplitude } => {
+        let offset = rand::thread_rng().gen_range(-1.0f64..=1.0f64);
+        let value = Decimal::from_f64_retain(offset)
+          .and_then(|offset| amplitude.checked_mul(offset))
+          .and_then(|offset| mean.checked_add(offset))
+          .unwrap_or(*mean);
+        Sample::Number(value)
+      }
+      config::SimulatorValue::Counter { start, step } => {
+        let value = Decimal::from(milliseconds)
+          .checked_div(Decimal::ONE_THOUSAND)
+          .and_then(|seconds| step.checked_mul(seconds))
+          .and_then(|offset| start.checked_add(offset))
+          .unwrap_or(*start);
+        Sample::Number(value)
+      }
+    }
+  }
+}
+
+fn default_value(
+  register: &modbus::MeasurementRegister<modbus::RegisterKindStorage>,
+) -> config::SimulatorValue {
+  match &register.storage {
+    modbus::RegisterKindStorage::String(_) => {
+      config::SimulatorValue::Text(register.name.clone())
+    }
+    modbus::RegisterKindStorage::Enum(kind) => {
+      config::SimulatorValue::Constant(
+        kind
+          .variants
+          .keys()
+          .next()
+          .copied()
+          .map(Decimal::from)
+          .unwrap_or_default(),
+      )
+    }
+    modbus::RegisterKindStorage::Bitfield(_) => {
+      config::SimulatorValue::Constant(Decimal::ZERO)
+    }
+    modbus::RegisterKindStorage::DateTime(_)
+    | modbus::RegisterKindStorage::Iec870Time(_) => {
+      config::SimulatorValue::Text("now".to_string())
+    }
+    _ => config::SimulatorValue::Ramp {
+      min: Decimal::ZERO,
+      max: Decimal::ONE_HUNDRED,
+      period: chrono::Duration::minutes(1),
+    },
+  }
+}
+
+// NOTE: this only covers the literal heavy patterns detect registers use
+// so anything fancier should set the simulator detect values explicitly
+fn sample_match(pattern: &str) -> String {
+  sample_sequence(&mut pattern.chars().peekable())
+}
+
+// NOTE: samples the first alternative up to the closing parenthesis
+// of the current group and treats nested groups as single atoms
+fn sample_sequence(chars: &mut Peekable<Chars>) -> String {
+  let mut sample = String::new();
+
+  while let Some(char) = chars.next() {
+    let atom = match char {
+      '^' | '$' => continue,
+      ')' => break,
+      '|' => {
+        skip_alternatives(chars);
+        break;
+      }
+      '(' => {
+        if chars.next_if_eq(&'?').is_some() {
+          let _ = chars.next_if_eq(&':');
+        }
+        sample_sequence(chars)
+      }
+      '\\' => match chars.next() {
+        Some('d') => "0".to_string(),
+        Some('w') => "a".to_string(),
+        Some('s') => " ".to_string(),
+        Some(char) => char.to_string(),
+        None => break,
+      },
+      '[' => sample_class(chars).to_string(),
+      '.' => "a".to_string(),
+      char => char.to_string(),
+    };
+    sample.push_str(quantify(atom, chars).as_str());
+  }
+
+  sample
+}
+
+fn skip_alternatives(chars: &mut Peekable<Chars>) {
+  let mut depth = 0usize;
+  while let Some(char) = chars.next() {
+    match char {
+      '\\' => {
+        let _ = chars.next();
+      }
+      '[' => {
+        let _ = sample_class(chars);
+      }
+      '(' => depth = depth.saturating_add(1),
+      ')' => match depth.checked_sub(1) {
+        Some(outer) => depth = outer,
+        None => break,
+      },
+      _ => {}
+    }
+  }
+}
+
+fn sample_class(chars: &mut Peekable<Chars>) -> char {
+  let negated = chars.next_if_eq(&'^').is_some();
+  let mut class = String::new();
+  if let Some(bracket) = chars.next_if_eq(&']') {
+    class.push(bracket);
+  }
+  while let Some(char) = chars.next() {
+    match char {
+      ']' => break,
+      '\\' => match chars.next() {
+        Some('d') => class.push('0'),
+        Some('w') => class.push('a'),
+        Some('s') => class.push(' '),
+        Some(char) => class.push(char),
+        None => break,
+      },
+      char => class.push(char),
+    }
+  }
+
+  if negated {
+    ('a'..='z')
+      .chain('0'..='9')
+      .find(|char| !class.contains(*char))
+      .unwrap_or('_')
+  } else {
+    class.chars().next().unwrap_or('a')
+  }
+}
+
+fn quantify(atom: String, chars: &mut Peekable<Chars>) -> String {
+  let repeat = match chars.peek() {
+    Some('?' | '*') => {
+      let _ = chars.next();
+      0
+    }
+    Some('+') => {
+      let _ = chars.next();
+      1
+    }
+    Some('{') => {
+      let _ = chars.next();
+      let repeat = chars
+        .by_ref()
+        .take_while(|char| *char != '}')
+        .collect::<String>();
+      repeat
+        .split(',')
+        .next()
+        .and_then(|repeat| repeat.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+    }
+    _ => return atom,
+  };
+  // NOTE: lazy quantifiers sample the same as greedy ones
+  let _ = chars.next_if_eq(&'?');
+
+  atom.repeat(repeat)
+}
+
+fn roll(probability: f64) -> bool {
+  probability > 0.0 && rand::thread_rng().gen_bool(probability)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn samples_quantified_groups() {
+    for pattern in [
+      "^iEM3(ab)?[0-9]{3}$",
+      "^(ab)*c$",
+      "^(?:ab){2}c$",
+      "^(foo|bar)+-[^0-9]\\d$",
+      "^x(a|(b|c))?y$",
+    ] {
+      let sample = sample_match(pattern);
+      #[allow(clippy::unwrap_used, reason = "test patterns are valid")]
+      let regex = regex::Regex::new(pattern).unwrap();
+      assert!(regex.is_match(sample.as_str()), "{pattern} -> {sample}");
+    }
+  }
+
+  #[test]
+  fn samples_every_bundled_detect_pattern() {
+    #[allow(clippy::unwrap_used, reason = "bundled config is valid")]
+    let values = include_str!("../../../../assets/pidgeon/config.toml")
+      .parse::<toml::Table>()
+      .unwrap();
+    let devices = values
+      .get("modbus")
+      .and_then(|modbus| modbus.get("devices"))
+      .and_then(toml::Value::as_table)
+      .cloned()
+      .unwrap_or_default();
+    assert!(!devices.is_empty());
+
+    for (kind, device) in devices {
+      let alternatives = device
+        .get("alternatives")
+        .and_then(toml::Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+      let patterns = std::iter::once(&device)
+        .chain(alternatives.iter())
+        .filter_map(|detect| detect.get("detect"))
+        .filter_map(toml::Value::as_array)
+        .flatten()
+        .filter_map(|register| register.get("match"))
+        .filter_map(toml::Value::as_str);
+      for pattern in patterns {
+        let Ok(regex) = regex::Regex::new(pattern) else {
+          continue;
+        };
+        let sample = sample_match(pattern);
+        assert!(
+          regex.is_match(sample.as_str()),
+          "{kind}: {pattern} -> {sample}"
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,207 @@
+mod device;
+
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+use futures::future::{try_join_all, BoxFuture, FutureExt};
+use tokio_modbus::prelude::*;
+use tokio_serial::SerialPort;
+
+use crate::config;
+
+// NOTE: the tokio-modbus server framing always echoes the request header
+// so the wrong slave fault answers requests meant for other slaves instead
+
+const BROADCAST_SLAVE: u8 = 0;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RunError {
+  #[error("Unknown device kind {0}")]
+  UnknownKind(String),
+
+  #[error("Duplicate slave {0}")]
+  DuplicateSlave(u8),
+
+  #[error("Failed binding tcp listener")]
+  Bind(#[source] std::io::Error),
+
+  #[error("Failed opening pseudo terminal")]
+  Pty(#[from] tokio_serial::Error),
+
+  #[error("Failed linking pseudo terminal")]
+  Link(#[source] std::io::Error),
+
+  #[error("Server stopped")]
+  Serve(#[source] std::io::Error),
+}
+
+#[derive(Debug, Clone)]
+struct Service {
+  devices: Arc<HashMap<u8, device::Device>>,
+}
+
+impl Service {
+  fn new(
+    devices: &HashMap<String, config::Device>,
+    simulated: Vec<config::SimulatorDevice>,
+  ) -> Result<Self, RunError> {
+    let mut slaves = HashMap::new();
+    for simulated in simulated {
+      let device = match devices.get(&simulated.kind) {
+        Some(device) => device::Device::new(device, simulated),
+        None => return Err(RunError::UnknownKind(simulated.kind)),
+      };
+      let slave = device.slave();
+      if slaves.insert(slave, device).is_some() {
+        return Err(RunError::DuplicateSlave(slave));
+      }
+    }
+
+    Ok(Self {
+      devices: Arc::new(slaves),
+    })
+  }
+
+  fn respond(&self, request: SlaveRequest<'static>) -> Option<Response> {
+    let SlaveRequest { slave, request } = request;
+
+    if slave == BROADCAST_SLAVE {
+      for device in self.devices.values() {
+        device.broadcast(&request);
+      }
+      return None;
+    }
+
+    match self.devices.get(&slave) {
+      Some(device) => device.respond(request),
+      None => {
+        let device = self
+          .devices
+          .values()
+          .find(|device| device.answers_wrong_slave())?;
+        tracing::debug!(
+          "Injecting answer from slave {} for slave {}",
+          device.slave(),
+          slave
+        );
+        device.respond(request)
+      }
+    }
+  }
+}
+
+impl tokio_modbus::server::Service for Service {
+  type Request = SlaveRequest<'static>;
+  type Response = Option<Response>;
+  type Error = std::io::Error;
+  type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+  fn call(&self, request: Self::Request) -> Self::Future {
+    std::future::ready(Ok(self.respond(request)))
+  }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn run(simulation: config::Simulation) -> anyhow::Result<()> {
+  if simulation.servers.is_empty() {
+    tracing::warn!("No simulator servers configured");
+    return Ok(());
+  }
+
+  let mut servers = Vec::new();
+  for server in simulation.servers {
+    let service = Service::new(&simulation.devices, server.devices)?;
+    servers.push(match server.transport {
+      config::SimulatorTransport::Tcp(address) => {
+        serve_tcp(address, service).await?
+      }
+      config::SimulatorTransport::Rtu(link) => serve_rtu(link, service).await?,
+    });
+  }
+
+  tokio::select! {
+    result = try_join_all(servers) => {
+      result?;
+    }
+    result = tokio::signal::ctrl_c() => {
+      if let Err(error) = result {
+        tracing::error!("Failed waiting for ctrlc signal {}", error);
+      }
+    }
+  }
+
+  Ok(())
+}
+
+async fn serve_tcp(
+  address: SocketAddr,
+  service: Service,
+) -> Result<BoxFuture<'static, Result<(), RunError>>, RunError> {
+  let listener = tokio::net::TcpListener::bind(address)
+    .await
+    .map_err(RunError::Bind)?;
+  tracing::info!(
+    "Simulating slaves {:?} over tcp on {}",
+    service.devices.keys().collect::<Vec<_>>(),
+    address
+  );
+
+  let server = tokio_modbus::server::tcp::Server::new(listener);
+  Ok(
+    async move {
+      let on_connected = |stream, address| {
+        let service = service.clone();
+        async move {
+          tokio_modbus::server::tcp::accept_tcp_connection(
+            stream,
+            address,
+            |_| Ok(Some(service.clone())),
+          )
+        }
+      };
+      let on_process_error = |error| {
+        tracing::warn!("Failed processing tcp connection {}", error);
+      };
+      server
+        .serve(&on_connected, on_process_error)
+        .await
+        .map_err(RunError::Serve)
+    }
+    .boxed(),
+  )
+}
+
+// NOTE: the slave end stays open for the lifetime of the server
+// because the master end starts failing as soon as it gets closed
+async fn serve_rtu(
+  link: Option<String>,
+  service: Service,
+) -> Result<BoxFuture<'static, Result<(), RunError>>, RunError> {
+  let (master, slave) = tokio_serial::SerialStream::pair()?;
+  let path = slave.name().unwrap_or_default();
+  if let Some(link) = &link {
+    match tokio::fs::remove_file(link).await {
+      Ok(()) => {}
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+      Err(error) => return Err(RunError::Link(error)),
+    }
+    tokio::fs::symlink(&path, link)
+      .await
+      .map_err(RunError::Link)?;
+  }
+  tracing::info!(
+    "Simulating slaves {:?} over rtu on {} linked from {:?}",
+    service.devices.keys().collect::<Vec<_>>(),
+    path,
+    link
+  );
+
+  let server = tokio_modbus::server::rtu::Server::new(master);
+  Ok(
+    async move {
+      let result = server.serve_forever(service).await;
+      drop(slave);
+      result.map_err(RunError::Serve)
+    }
+    .boxed(),
+  )
+}